@@ -2,11 +2,9 @@
 
 pub type BoardCoord = u8;
 
-//in cells
-pub const BOARD_WIDTH: BoardCoord = 6;
-pub const BOARD_HEIGHT: BoardCoord = 6;
-
-pub const BOARD_LENGTH: usize = BOARD_WIDTH as usize * BOARD_HEIGHT as usize;
+//in cells, for levels that don't say otherwise
+pub const DEFAULT_BOARD_WIDTH: BoardCoord = 6;
+pub const DEFAULT_BOARD_HEIGHT: BoardCoord = 6;
 
 //in pixels
 pub const SCREEN_WIDTH: usize = 256;
@@ -21,8 +19,14 @@ pub const YELLOW: u32 = 0xFF37B9FF;
 pub const PURPLE: u32 = 0xFF543353;
 pub const GREY: u32 = 0xFF8B7D5A;
 pub const GRAY: u32 = GREY;
+pub const WHITE: u32 = 0xFFEEEEEE;
+pub const BLACK: u32 = 0xFF111111;
 
 pub const FLOOR: u32 = PURPLE;
+pub const WALL: u32 = GREY;
+pub const PIT: u32 = BLACK;
+pub const ICE: u32 = 0xFFE8C89A;
+pub const CHARGING_PAD: u32 = YELLOW;
 
 //in pixels
 pub const CELL_WIDTH: usize = 32;
@@ -30,12 +34,32 @@ pub const CELL_HEIGHT: usize = 32;
 pub const CELL_DIAMETER: usize = CELL_WIDTH;
 pub const CELL_RADIUS: usize = CELL_DIAMETER / 2;
 
-//A spacer pixel after the last cell.
-pub const HUD_LEFT_EDGE: usize = ((BOARD_WIDTH as usize) * (CELL_WIDTH + 1) + 1) + 1;
-pub const HUD_WIDTH: usize = SCREEN_WIDTH - HUD_LEFT_EDGE;
+pub const MIN_HUD_WIDTH: usize = 48;
+pub const MAX_INVENTORY_WIDTH: usize = 24;
+
+//in cells, the most that fits on the screen next to the HUD
+pub const MAX_BOARD_WIDTH: BoardCoord =
+    ((SCREEN_WIDTH - MIN_HUD_WIDTH - 2) / (CELL_WIDTH + 1)) as BoardCoord;
+pub const MAX_BOARD_HEIGHT: BoardCoord = ((SCREEN_HEIGHT - 1) / (CELL_HEIGHT + 1)) as BoardCoord;
 
-pub const INVENTORY_WIDTH: usize = HUD_WIDTH / 3;
-pub const INVENTORY_HEIGHT: usize = INVENTORY_WIDTH;
-pub const INVENTORY_LEFT_EDGE: usize = HUD_LEFT_EDGE + INVENTORY_WIDTH;
+pub const INVENTORY_SIZE: usize = 3;
 
 pub const ORB_RADIUS: usize = CELL_DIAMETER / 9;
+
+//in world turns
+pub const FULL_CHARGE: u8 = 8;
+
+//in pixels
+pub const CHARGE_BAR_HEIGHT: usize = 4;
+pub const DIGIT_WIDTH: usize = 3;
+pub const DIGIT_HEIGHT: usize = 5;
+
+//The first three are Red: Empathy, White: Rules and Black: Self-interest, see Design.md.
+pub const ASPECT_COLOURS: [u32; 6] = [RED, WHITE, BLACK, GREEN, YELLOW, BLUE];
+pub const MAX_ASPECTS: u8 = ASPECT_COLOURS.len() as u8;
+pub const DEFAULT_ASPECT_COUNT: u8 = 3;
+
+pub const MAX_HEALTH: u8 = 3;
+
+//in frames
+pub const BEAM_FRAMES: u8 = 12;
@@ -0,0 +1,239 @@
+use super::*;
+
+//Layouts that shut the avatar in get thrown away and rolled again, this many times before
+//giving up and clearing the terrain.
+const MAX_ATTEMPTS: usize = 64;
+
+//in cells
+const MIN_BOARD_SIZE: BoardCoord = 4;
+
+const MIN_ORBS: usize = 6;
+const MAX_EXTRA_ORBS: usize = 6;
+//how many random slots to try for each orb
+const SLOT_ATTEMPTS: usize = 16;
+
+//A fresh board, which will be the same every time for the same seed.
+pub fn generate(seed: u32) -> GameState {
+    let mut rng = Rng::new(seed);
+
+    let width = MIN_BOARD_SIZE + rng.below((MAX_BOARD_WIDTH - MIN_BOARD_SIZE + 1) as usize) as u8;
+    let height = MIN_BOARD_SIZE + rng.below((MAX_BOARD_HEIGHT - MIN_BOARD_SIZE + 1) as usize) as u8;
+    let board = Board::new(width, height).unwrap_or_default();
+
+    let mut state = GameState::blank();
+    for attempt in 0..MAX_ATTEMPTS {
+        state = GameState::blank();
+        state.set_board(board);
+
+        if attempt < MAX_ATTEMPTS - 1 {
+            lay_out_terrain(&mut state, &mut rng);
+        }
+
+        place_avatar(&mut state, &mut rng);
+
+        if !is_avatar_enclosed(&state) && !open_cells(&state).is_empty() {
+            break;
+        }
+    }
+
+    place_orbs(&mut state, &mut rng);
+
+    for _ in 0..2 {
+        state.inventory.add(InventoryItem {
+            orb_type: OrbType::DeadOrb,
+            aspect: Aspect(rng.below(state.aspect_count as usize) as u8),
+            health: MAX_HEALTH,
+            ..InventoryItem::default()
+        });
+    }
+
+    state.seed = Some(seed);
+    state.rng = rng;
+    state.record_turn();
+
+    state
+}
+
+fn lay_out_terrain(state: &mut GameState, rng: &mut Rng) {
+    for tile in state.terrain.iter_mut() {
+        //out of 20
+        *tile = match rng.below(20) {
+            0..=2 => Terrain::Wall,
+            3 => Terrain::Pit,
+            4 => Terrain::Ice,
+            5 => Terrain::ChargingPad,
+            _ => Terrain::Floor,
+        };
+    }
+}
+
+fn is_walkable(terrain: Terrain) -> bool {
+    !terrain.blocks_movement() && !terrain.swallows_orbs()
+}
+
+fn place_avatar(state: &mut GameState, rng: &mut Rng) {
+    let floors: Vec<usize> = (0..state.board.length())
+        .filter(|&i| state.terrain[i] == Terrain::Floor)
+        .collect();
+
+    let i = if floors.is_empty() {
+        state.terrain[0] = Terrain::Floor;
+        0
+    } else {
+        floors[rng.below(floors.len())]
+    };
+
+    if let Some(pos) = state.board.xy(i) {
+        state.positions[state.avatarId] = pos;
+    }
+}
+
+fn has_orbs(state: &GameState, pos: Position) -> bool {
+    (0..GameState::ENTITY_COUNT).any(|id| state.is_orb(id) && state.positions[id] == pos)
+}
+
+//Cells the avatar could stand in without having to get any orbs out of the way first.
+fn is_open(state: &GameState, pos: Position) -> bool {
+    is_walkable(state.terrain_at(pos)) && !has_orbs(state, pos)
+}
+
+fn open_cells(state: &GameState) -> Vec<Position> {
+    state
+        .board
+        .cells()
+        .into_iter()
+        .filter(|&pos| is_open(state, pos) && pos != state.positions[state.avatarId])
+        .collect()
+}
+
+//Whether there are any open cells the avatar can't walk to. Orbs count as being in the way,
+//since the avatar can't always pick them up or push them.
+fn is_avatar_enclosed(state: &GameState) -> bool {
+    let mut reached = vec![false; state.board.length()];
+    let mut stack = vec![state.positions[state.avatarId]];
+
+    while let Some(pos) = stack.pop() {
+        let i = match state.board.index(pos.0, pos.1) {
+            Some(i) => i,
+            None => continue,
+        };
+
+        let is_avatar = pos == state.positions[state.avatarId];
+        if reached[i] || !(is_avatar || is_open(state, pos)) {
+            continue;
+        }
+        reached[i] = true;
+
+        stack.extend(
+            Direction::ALL
+                .iter()
+                .filter_map(|direction| direction.step(state.board, pos)),
+        );
+    }
+
+    open_cells(state)
+        .into_iter()
+        .filter_map(|pos| state.board.index(pos.0, pos.1))
+        .any(|i| !reached[i])
+}
+
+fn place_orbs(state: &mut GameState, rng: &mut Rng) {
+    let cells: Vec<Position> = (0..state.board.length())
+        .filter(|&i| is_walkable(state.terrain[i]))
+        .filter_map(|i| state.board.xy(i))
+        .filter(|&pos| pos != state.positions[state.avatarId])
+        .collect();
+
+    if cells.is_empty() {
+        return;
+    }
+
+    let count = MIN_ORBS + rng.below(MAX_EXTRA_ORBS + 1);
+
+    for k in 0..count {
+        let orb_type = if rng.below(3) == 0 {
+            OrbType::LiveOrb
+        } else {
+            OrbType::DeadOrb
+        };
+
+        let aspect_count = state.aspect_count as usize;
+        let aspect = if k < aspect_count {
+            Aspect(k as u8)
+        } else {
+            Aspect(rng.below(aspect_count) as u8)
+        };
+
+        let placed = (0..SLOT_ATTEMPTS).any(|_| {
+            let pos = cells[rng.below(cells.len())];
+            let slot = Nine(_3by3::ALL[rng.below(_3by3::ALL.len())]);
+
+            try_to_place(state, orb_type, pos, slot, aspect)
+        });
+
+        //Every aspect gets at least one orb, so it's possible to make something of them all.
+        //There's always somewhere to put these first few, since the layout was only kept if
+        //it had an open cell, and an open cell can hold one of each aspect.
+        if !placed && k < aspect_count {
+            let slots = cells
+                .iter()
+                .flat_map(|&pos| _3by3::ALL.iter().map(move |&slot| (pos, Nine(slot))));
+
+            for (pos, slot) in slots {
+                if try_to_place(state, orb_type, pos, slot, aspect) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+//Puts the orb there, unless that slot is taken or it would shut the avatar in.
+fn try_to_place(
+    state: &mut GameState,
+    orb_type: OrbType,
+    pos: Position,
+    slot: IntraCellPosition,
+    aspect: Aspect,
+) -> bool {
+    let taken = (0..GameState::ENTITY_COUNT).any(|id| {
+        state.is_orb(id) && state.positions[id] == pos
+            && state.intra_cell_positions[id].overlaps(&slot)
+    });
+    if taken {
+        return false;
+    }
+
+    match state.add_orb(orb_type, pos, slot, aspect) {
+        Some(id) if is_avatar_enclosed(state) => {
+            state.remove_entity(id);
+            false
+        }
+        Some(_) => true,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod generated_boards {
+    use super::*;
+
+    quickcheck! {
+        fn are_the_same_for_the_same_seed(seed: u32) -> bool {
+            level::export(&generate(seed)) == level::export(&generate(seed))
+        }
+
+        fn never_enclose_the_avatar(seed: u32) -> bool {
+            !is_avatar_enclosed(&generate(seed))
+        }
+
+        fn have_an_orb_of_every_aspect(seed: u32) -> bool {
+            let state = generate(seed);
+            let aspects = (0..GameState::ENTITY_COUNT)
+                .filter(|&id| state.is_orb(id))
+                .fold(0, |mask, id| mask | 1 << state.aspects[id].0);
+
+            aspects == state.all_aspects_mask()
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+use std::collections::VecDeque;
+use std::mem;
+
+//How many world turns back you can undo.
+pub const MAX_HISTORY: usize = 256;
+
+//Snapshots of the `GameState` as of each world turn. The last one in `past` is the current
+//turn, and `future` holds the turns that were undone, most recently undone last.
+#[derive(Clone, Default)]
+pub struct History {
+    past: VecDeque<GameState>,
+    future: Vec<GameState>,
+}
+
+impl History {
+    //Anything that was undone is gone for good once a new turn is taken.
+    pub fn record(&mut self, snapshot: GameState) {
+        self.past.push_back(snapshot);
+        self.future.clear();
+
+        //+ 1 for the current turn
+        while self.past.len() > MAX_HISTORY + 1 {
+            self.past.pop_front();
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<GameState> {
+        if self.past.len() < 2 {
+            return None;
+        }
+
+        let current = self.past.pop_back()?;
+        self.future.push(current);
+
+        self.past.back().cloned()
+    }
+
+    pub fn redo(&mut self) -> Option<GameState> {
+        let next = self.future.pop()?;
+        self.past.push_back(next.clone());
+
+        Some(next)
+    }
+}
+
+impl GameState {
+    pub fn record_turn(&mut self) {
+        let snapshot = self.without_history();
+        self.history.record(snapshot);
+    }
+
+    //A copy to try things out on, or to keep as a snapshot, which don't need a history of their
+    //own. Much cheaper than `clone` once the history has filled up.
+    pub fn without_history(&self) -> GameState {
+        GameState {
+            terrain: self.terrain.clone(),
+            beams: self.beams.clone(),
+            history: History::default(),
+            ..*self
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.restore_with(History::undo)
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.restore_with(History::redo)
+    }
+
+    fn restore_with(&mut self, step: fn(&mut History) -> Option<GameState>) -> bool {
+        let mut history = mem::take(&mut self.history);
+
+        let restored = match step(&mut history) {
+            Some(snapshot) => {
+                *self = snapshot;
+
+                //show where everything is straight away rather than sliding back to it
+                for appearance in self.appearances.iter_mut() {
+                    appearance.offset = (0, 0);
+                }
+                self.beams.clear();
+                self.turn_pending = false;
+
+                true
+            }
+            None => false,
+        };
+
+        self.history = history;
+
+        restored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take_turn(state: &mut GameState) {
+        state.turn += 1;
+        state.record_turn();
+    }
+
+    #[test]
+    fn undo_then_redo_gets_back_to_the_same_turn() {
+        let mut state = GameState::new();
+        take_turn(&mut state);
+        take_turn(&mut state);
+
+        assert!(state.undo());
+        assert_eq!(state.turn, 1);
+        assert!(state.undo());
+        assert_eq!(state.turn, 0);
+        assert!(!state.undo());
+
+        assert!(state.redo());
+        assert!(state.redo());
+        assert_eq!(state.turn, 2);
+        assert!(!state.redo());
+    }
+
+    #[test]
+    fn a_new_turn_clears_the_redos() {
+        let mut state = GameState::new();
+        take_turn(&mut state);
+
+        assert!(state.undo());
+        take_turn(&mut state);
+
+        assert!(!state.redo());
+    }
+}
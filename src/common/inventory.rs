@@ -0,0 +1,132 @@
+use super::*;
+
+//Everything needed to put an orb back on the board the way it was picked up.
+#[derive(Clone, Copy, Default)]
+pub struct InventoryItem {
+    pub orb_type: OrbType,
+    pub aspect: Aspect,
+    pub charge: u8,
+    pub health: u8,
+    pub constituents: Constituents,
+}
+
+impl InventoryItem {
+    pub fn is_empty(&self) -> bool {
+        self.orb_type == OrbType::NoOrb
+    }
+
+    //How it looks in the HUD, which is the same as on the board.
+    pub fn appearance(&self) -> Option<Appearance> {
+        let shape = self.orb_type.shape()?;
+
+        Some(Appearance {
+            colour: self.aspect.colour(),
+            shape,
+            offset: (0, 0),
+            charge: self.charge,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Inventory {
+    items: [InventoryItem; INVENTORY_SIZE],
+    //the slot that gets thrown next
+    pub index: u8,
+}
+
+impl Inventory {
+    pub fn get(&self, index: usize) -> &InventoryItem {
+        &self.items[index]
+    }
+
+    pub fn free_slots(&self) -> usize {
+        self.items.iter().filter(|item| item.is_empty()).count()
+    }
+
+    //Puts the item in the first empty slot, returning whether there was one.
+    pub fn add(&mut self, item: InventoryItem) -> bool {
+        match self.items.iter().position(|item| item.is_empty()) {
+            Some(slot) => {
+                self.items[slot] = item;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set(&mut self, index: usize, item: InventoryItem) {
+        self.items[index] = item;
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<InventoryItem> {
+        let item = self.items[index];
+
+        if item.is_empty() {
+            None
+        } else {
+            self.items[index] = InventoryItem::default();
+            Some(item)
+        }
+    }
+
+    pub fn selected(&self) -> &InventoryItem {
+        self.get(self.index as usize)
+    }
+
+    pub fn remove_selected(&mut self) -> Option<InventoryItem> {
+        let index = self.index as usize;
+        self.remove(index)
+    }
+
+    //Moves on to the next slot with something in it, staying put if there isn't one.
+    pub fn select_next(&mut self) {
+        let index = self.index as usize;
+
+        if let Some(next) = (1..=INVENTORY_SIZE)
+            .map(|offset| (index + offset) % INVENTORY_SIZE)
+            .find(|&i| !self.items[i].is_empty())
+        {
+            self.index = next as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dead_orb(aspect: Aspect) -> InventoryItem {
+        InventoryItem {
+            orb_type: OrbType::DeadOrb,
+            aspect,
+            ..InventoryItem::default()
+        }
+    }
+
+    #[test]
+    fn select_next_skips_empty_slots() {
+        let mut inventory = Inventory::default();
+        inventory.add(dead_orb(Aspect::Red));
+        inventory.add(dead_orb(Aspect::White));
+        inventory.add(dead_orb(Aspect::Black));
+        inventory.remove(1);
+
+        inventory.select_next();
+        assert_eq!(inventory.index, 2);
+
+        inventory.select_next();
+        assert_eq!(inventory.index, 0);
+    }
+
+    #[test]
+    fn select_next_stays_put_when_empty() {
+        let mut inventory = Inventory {
+            index: 1,
+            ..Inventory::default()
+        };
+
+        inventory.select_next();
+        assert_eq!(inventory.index, 1);
+    }
+}
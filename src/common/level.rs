@@ -0,0 +1,733 @@
+//A plain text level format. Each line is a keyword followed by its values, separated by
+//whitespace. Blank lines and lines starting with `;` are ignored.
+//
+//    seed <n>                         for boards made by `generator::generate`
+//    aspects <count>                  before any orbs, defaults to 3
+//    board <width> <height>           before anything that goes on the board, defaults to 6 by 6
+//    chained-pushes                   lets pushed orbs push the orbs in front of them along
+//    terrain                          followed by one row of terrain characters per board row
+//    avatar <x> <y>
+//    orb <kind> <aspect> <x> <y> <four|nine> <slot x> <slot y> [details]
+//    inventory <kind> <aspect> [details]
+//    inventory empty
+//
+//where the optional details are `charge <n>`, `health <n>` and lastly `of <kind>-<aspect>...`
+//for what a blob or created orb is made of. `charge` and `health` default to what a newly
+//made orb of that kind has. Terrain characters are `.` floor, `#` wall, `O` pit, `~` ice and
+//`+` charging pad.
+
+use super::*;
+
+use std::error::Error;
+use std::fmt;
+
+pub const BUNDLED_LEVELS: [&str; 1] = [include_str!("../../levels/first.level")];
+
+const ASPECT_NAMES: [&str; MAX_ASPECTS as usize] =
+    ["red", "white", "black", "green", "yellow", "blue"];
+
+const ORB_TYPES: [(&str, OrbType); 4] = [
+    ("dead", OrbType::DeadOrb),
+    ("live", OrbType::LiveOrb),
+    ("blob", OrbType::Blob),
+    ("created", OrbType::CreatedOrb),
+];
+
+const TERRAIN_CHARS: [(char, Terrain); 5] = [
+    ('.', Terrain::Floor),
+    ('#', Terrain::Wall),
+    ('O', Terrain::Pit),
+    ('~', Terrain::Ice),
+    ('+', Terrain::ChargingPad),
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    UnknownKeyword(String),
+    UnknownOrbType(String),
+    UnknownAspect(String),
+    UnknownSlotKind(String),
+    UnknownTerrain(char),
+    UnexpectedToken(String),
+    ExpectedNumber(String),
+    OutOfRange(String),
+    MissingValue,
+    WrongRowLength { expected: usize, found: usize },
+    BoardAfterContents,
+    AspectsAfterOrbs,
+    MissingRows,
+    TooManyEntities,
+    TooManyInventoryItems,
+}
+
+//`line` and `column` start from 1, and point at the start of the offending token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+
+        match self.kind {
+            ParseErrorKind::UnknownKeyword(ref s) => write!(f, "unknown keyword \"{}\"", s),
+            ParseErrorKind::UnknownOrbType(ref s) => write!(f, "unknown orb type \"{}\"", s),
+            ParseErrorKind::UnknownAspect(ref s) => write!(f, "unknown aspect \"{}\"", s),
+            ParseErrorKind::UnknownSlotKind(ref s) => {
+                write!(f, "expected \"four\" or \"nine\", found \"{}\"", s)
+            }
+            ParseErrorKind::UnknownTerrain(c) => write!(f, "unknown terrain '{}'", c),
+            ParseErrorKind::UnexpectedToken(ref s) => write!(f, "unexpected \"{}\"", s),
+            ParseErrorKind::ExpectedNumber(ref s) => {
+                write!(f, "expected a number, found \"{}\"", s)
+            }
+            ParseErrorKind::OutOfRange(ref s) => write!(f, "{} is out of range", s),
+            ParseErrorKind::MissingValue => {
+                write!(f, "expected a value before the end of the line")
+            }
+            ParseErrorKind::WrongRowLength { expected, found } => write!(
+                f,
+                "expected a row of {} terrain characters, found {}",
+                expected, found
+            ),
+            ParseErrorKind::BoardAfterContents => {
+                write!(f, "the board size has to come before what's on the board")
+            }
+            ParseErrorKind::AspectsAfterOrbs => {
+                write!(f, "the aspect count has to come before any orbs")
+            }
+            ParseErrorKind::MissingRows => write!(f, "the terrain needs more rows"),
+            ParseErrorKind::TooManyEntities => write!(f, "there's no room for another entity"),
+            ParseErrorKind::TooManyInventoryItems => {
+                write!(f, "the inventory only has {} slots", INVENTORY_SIZE)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+struct Tokens<'a> {
+    line: usize,
+    tokens: Vec<(usize, &'a str)>,
+    next: usize,
+    //where to point errors about the line ending too soon
+    end_column: usize,
+    //aspects from here on are out of range
+    aspect_count: u8,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: usize, text: &'a str, aspect_count: u8) -> Self {
+        let mut tokens = Vec::new();
+        let mut start = None;
+
+        for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(i),
+                (Some(s), true) => {
+                    tokens.push((s + 1, &text[s..i]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        Tokens {
+            line,
+            tokens,
+            next: 0,
+            end_column: text.trim_end().len() + 1,
+            aspect_count,
+        }
+    }
+
+    fn error_at(&self, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column,
+            kind,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.next).map(|&(_, token)| token)
+    }
+
+    fn next(&mut self) -> Result<(usize, &'a str), ParseError> {
+        match self.tokens.get(self.next) {
+            Some(&token) => {
+                self.next += 1;
+                Ok(token)
+            }
+            None => Err(self.error_at(self.end_column, ParseErrorKind::MissingValue)),
+        }
+    }
+
+    fn number(&mut self, max: usize, what: &str) -> Result<u8, ParseError> {
+        let (column, token) = self.next()?;
+
+        match token.parse::<usize>() {
+            Ok(n) if n <= max => Ok(n as u8),
+            Ok(_) => Err(self.error_at(column, ParseErrorKind::OutOfRange(what.to_string()))),
+            Err(_) => Err(self.error_at(column, ParseErrorKind::ExpectedNumber(token.to_string()))),
+        }
+    }
+
+    //like `number`, but zero is out of range too
+    fn size(&mut self, max: usize, what: &str) -> Result<u8, ParseError> {
+        let column = self.tokens
+            .get(self.next)
+            .map(|&(column, _)| column)
+            .unwrap_or(self.end_column);
+
+        match self.number(max, what)? {
+            0 => Err(self.error_at(column, ParseErrorKind::OutOfRange(what.to_string()))),
+            n => Ok(n),
+        }
+    }
+
+    fn seed(&mut self) -> Result<u32, ParseError> {
+        let (column, token) = self.next()?;
+
+        token
+            .parse()
+            .map_err(|_| self.error_at(column, ParseErrorKind::ExpectedNumber(token.to_string())))
+    }
+
+    fn position(&mut self, board: Board) -> Result<Position, ParseError> {
+        let x = self.number(board.width as usize - 1, "x")?;
+        let y = self.number(board.height as usize - 1, "y")?;
+
+        Ok((x, y))
+    }
+
+    fn orb_type(&mut self) -> Result<OrbType, ParseError> {
+        let (column, token) = self.next()?;
+
+        parse_orb_type(token)
+            .ok_or_else(|| self.error_at(column, ParseErrorKind::UnknownOrbType(token.to_string())))
+    }
+
+    fn aspect(&mut self) -> Result<Aspect, ParseError> {
+        let (column, token) = self.next()?;
+
+        let aspect = parse_aspect(token).ok_or_else(|| {
+            self.error_at(column, ParseErrorKind::UnknownAspect(token.to_string()))
+        })?;
+
+        self.check_aspect(column, aspect)
+    }
+
+    fn check_aspect(&self, column: usize, aspect: Aspect) -> Result<Aspect, ParseError> {
+        if aspect.0 < self.aspect_count {
+            Ok(aspect)
+        } else {
+            Err(self.error_at(column, ParseErrorKind::OutOfRange("aspect".to_string())))
+        }
+    }
+
+    fn intra_cell_position(&mut self) -> Result<IntraCellPosition, ParseError> {
+        let (column, kind) = self.next()?;
+
+        match kind {
+            "four" => {
+                let xy = (self.number(1, "slot x")?, self.number(1, "slot y")?);
+                let slot = _2by2::ALL.iter().cloned().find(|slot| slot.xy() == xy);
+
+                Ok(Four(slot.unwrap_or(_2by2::_0_0)))
+            }
+            "nine" => {
+                let (x, y) = (self.number(2, "slot x")?, self.number(2, "slot y")?);
+
+                Ok(Nine(_3by3::from_xy(x, y)))
+            }
+            _ => Err(self.error_at(column, ParseErrorKind::UnknownSlotKind(kind.to_string()))),
+        }
+    }
+
+    //The optional `charge`, `health` and `of` parts at the end of orb and inventory lines.
+    fn orb_details(&mut self, item: &mut InventoryItem) -> Result<(), ParseError> {
+        while let Some(keyword) = self.peek() {
+            let (column, _) = self.next()?;
+
+            match keyword {
+                "charge" => item.charge = self.number(FULL_CHARGE as usize, "charge")?,
+                "health" => item.health = self.number(MAX_HEALTH as usize, "health")?,
+                "of" => {
+                    let mut constituents = Constituents::default();
+
+                    while let Ok((column, token)) = self.next() {
+                        let constituent = parse_constituent(token).ok_or_else(|| {
+                            let kind = ParseErrorKind::UnexpectedToken(token.to_string());
+                            self.error_at(column, kind)
+                        })?;
+                        self.check_aspect(column, constituent.aspect)?;
+
+                        if !constituents.push(constituent) {
+                            return Err(self.error_at(
+                                column,
+                                ParseErrorKind::OutOfRange("constituent count".to_string()),
+                            ));
+                        }
+                    }
+
+                    item.constituents = constituents;
+                }
+                _ => {
+                    return Err(
+                        self.error_at(column, ParseErrorKind::UnexpectedToken(keyword.to_string()))
+                    )
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn item(&mut self) -> Result<InventoryItem, ParseError> {
+        let orb_type = self.orb_type()?;
+        let aspect = self.aspect()?;
+
+        let mut item = InventoryItem {
+            orb_type,
+            aspect,
+            charge: default_charge(orb_type),
+            health: MAX_HEALTH,
+            constituents: Constituents::default(),
+        };
+
+        self.orb_details(&mut item)?;
+
+        Ok(item)
+    }
+
+    fn finish(&mut self) -> Result<(), ParseError> {
+        match self.tokens.get(self.next) {
+            Some(&(column, token)) => {
+                Err(self.error_at(column, ParseErrorKind::UnexpectedToken(token.to_string())))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+fn default_charge(orb_type: OrbType) -> u8 {
+    if orb_type == OrbType::LiveOrb {
+        FULL_CHARGE
+    } else {
+        0
+    }
+}
+
+fn parse_orb_type(token: &str) -> Option<OrbType> {
+    ORB_TYPES
+        .iter()
+        .find(|&&(name, _)| name == token)
+        .map(|&(_, orb_type)| orb_type)
+}
+
+fn parse_aspect(token: &str) -> Option<Aspect> {
+    ASPECT_NAMES
+        .iter()
+        .position(|&name| name == token)
+        .map(|i| Aspect(i as u8))
+}
+
+//like "live-red"
+fn parse_constituent(token: &str) -> Option<Constituent> {
+    let mut parts = token.splitn(2, '-');
+    let orb_type = parse_orb_type(parts.next()?)?;
+    let aspect = parse_aspect(parts.next()?)?;
+
+    Some(Constituent { orb_type, aspect })
+}
+
+fn orb_type_name(orb_type: OrbType) -> &'static str {
+    ORB_TYPES
+        .iter()
+        .find(|&&(_, t)| t == orb_type)
+        .map(|&(name, _)| name)
+        .unwrap_or("none")
+}
+
+fn aspect_name(aspect: Aspect) -> &'static str {
+    ASPECT_NAMES.get(aspect.0 as usize).cloned().unwrap_or("none")
+}
+
+pub fn parse(text: &str) -> Result<GameState, ParseError> {
+    let mut state = GameState::blank();
+    let mut inventory_slot = 0;
+    //whether anything has been put on the board yet
+    let mut has_contents = false;
+    let mut has_orbs = false;
+
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    while let Some((line_number, line)) = lines.next() {
+        let mut tokens = Tokens::new(line_number, line, state.aspect_count);
+
+        let (column, keyword) = match tokens.peek() {
+            None => continue,
+            Some(keyword) if keyword.starts_with(';') => continue,
+            Some(_) => tokens.next()?,
+        };
+
+        match keyword {
+            "seed" => {
+                let seed = tokens.seed()?;
+                state.seed = Some(seed);
+                state.rng = Rng::new(seed);
+            }
+            "aspects" => {
+                if has_orbs {
+                    return Err(tokens.error_at(column, ParseErrorKind::AspectsAfterOrbs));
+                }
+
+                let count = tokens.number(MAX_ASPECTS as usize, "aspect count")?;
+                state.aspect_count = count;
+            }
+            "board" => {
+                if has_contents {
+                    return Err(tokens.error_at(column, ParseErrorKind::BoardAfterContents));
+                }
+
+                let width = tokens.size(MAX_BOARD_WIDTH as usize, "width")?;
+                let height = tokens.size(MAX_BOARD_HEIGHT as usize, "height")?;
+
+                state.set_board(Board { width, height });
+            }
+            "chained-pushes" => state.chained_pushes = true,
+            "terrain" => for y in 0..state.board.height {
+                has_contents = true;
+
+                let (row_number, row) = lines.next().ok_or_else(|| {
+                    tokens.error_at(tokens.end_column, ParseErrorKind::MissingRows)
+                })?;
+                let row = row.trim_end();
+
+                if row.chars().count() != state.board.width as usize {
+                    return Err(ParseError {
+                        line: row_number,
+                        column: 1,
+                        kind: ParseErrorKind::WrongRowLength {
+                            expected: state.board.width as usize,
+                            found: row.chars().count(),
+                        },
+                    });
+                }
+
+                for (x, c) in row.chars().enumerate() {
+                    let tile = TERRAIN_CHARS
+                        .iter()
+                        .find(|&&(ch, _)| ch == c)
+                        .map(|&(_, tile)| tile)
+                        .ok_or_else(|| ParseError {
+                            line: row_number,
+                            column: x + 1,
+                            kind: ParseErrorKind::UnknownTerrain(c),
+                        })?;
+
+                    if let Some(i) = state.board.index(x as BoardCoord, y) {
+                        state.terrain[i] = tile;
+                    }
+                }
+            },
+            "avatar" => {
+                has_contents = true;
+                let pos = tokens.position(state.board)?;
+                state.positions[state.avatarId] = pos;
+            }
+            "orb" => {
+                has_contents = true;
+                has_orbs = true;
+                let orb_type = tokens.orb_type()?;
+                let aspect = tokens.aspect()?;
+                let pos = tokens.position(state.board)?;
+                let intra_pos = tokens.intra_cell_position()?;
+
+                let mut details = InventoryItem {
+                    orb_type,
+                    aspect,
+                    charge: default_charge(orb_type),
+                    health: MAX_HEALTH,
+                    constituents: Constituents::default(),
+                };
+                tokens.orb_details(&mut details)?;
+
+                let id = state
+                    .add_orb(orb_type, pos, intra_pos, aspect)
+                    .ok_or_else(|| tokens.error_at(column, ParseErrorKind::TooManyEntities))?;
+
+                state.set_charge(id, details.charge);
+                state.healths[id] = details.health;
+                if !details.constituents.as_slice().is_empty() {
+                    state.entities[id].insert(Component::Constituents);
+                    state.constituents[id] = details.constituents;
+                }
+            }
+            "inventory" => {
+                has_orbs = true;
+                if inventory_slot >= INVENTORY_SIZE {
+                    return Err(tokens.error_at(column, ParseErrorKind::TooManyInventoryItems));
+                }
+
+                let item = if tokens.peek() == Some("empty") {
+                    tokens.next()?;
+                    InventoryItem::default()
+                } else {
+                    tokens.item()?
+                };
+
+                state.inventory.set(inventory_slot, item);
+                inventory_slot += 1;
+            }
+            _ => {
+                return Err(
+                    tokens.error_at(column, ParseErrorKind::UnknownKeyword(keyword.to_string()))
+                )
+            }
+        }
+
+        tokens.finish()?;
+    }
+
+    state.record_turn();
+
+    Ok(state)
+}
+
+fn write_details(
+    out: &mut String,
+    orb_type: OrbType,
+    charge: u8,
+    health: u8,
+    of: Option<&Constituents>,
+) {
+    if charge != default_charge(orb_type) {
+        out.push_str(&format!(" charge {}", charge));
+    }
+
+    if health != MAX_HEALTH {
+        out.push_str(&format!(" health {}", health));
+    }
+
+    if let Some(constituents) = of {
+        out.push_str(" of");
+
+        for constituent in constituents.as_slice() {
+            out.push_str(&format!(
+                " {}-{}",
+                orb_type_name(constituent.orb_type),
+                aspect_name(constituent.aspect)
+            ));
+        }
+    }
+}
+
+//Writes out the level as it stands, so `parse` gives back the same board.
+pub fn export(state: &GameState) -> String {
+    let mut out = String::new();
+
+    if let Some(seed) = state.seed {
+        out.push_str(&format!("seed {}\n", seed));
+    }
+    out.push_str(&format!("aspects {}\n", state.aspect_count));
+
+    if state.board != Board::default() {
+        out.push_str(&format!("board {} {}\n", state.board.width, state.board.height));
+    }
+
+    if state.chained_pushes {
+        out.push_str("chained-pushes\n");
+    }
+
+    out.push_str("terrain\n");
+    for y in 0..state.board.height {
+        for x in 0..state.board.width {
+            let tile = state.terrain_at((x, y));
+            let c = TERRAIN_CHARS
+                .iter()
+                .find(|&&(_, t)| t == tile)
+                .map(|&(c, _)| c)
+                .unwrap_or('.');
+
+            out.push(c);
+        }
+        out.push('\n');
+    }
+
+    let (x, y) = state.positions[state.avatarId];
+    out.push_str(&format!("avatar {} {}\n", x, y));
+
+    for id in 0..GameState::ENTITY_COUNT {
+        if !state.is_orb(id) {
+            continue;
+        }
+
+        let (x, y) = state.positions[id];
+        let (kind, (slot_x, slot_y)) = match state.intra_cell_positions[id] {
+            Four(slot) => ("four", slot.xy()),
+            Nine(slot) => ("nine", slot.xy()),
+        };
+
+        out.push_str(&format!(
+            "orb {} {} {} {} {} {} {}",
+            orb_type_name(state.orb_types[id]),
+            aspect_name(state.aspects[id]),
+            x,
+            y,
+            kind,
+            slot_x,
+            slot_y
+        ));
+
+        let of = if state.entities[id].contains(Component::Constituents) {
+            Some(&state.constituents[id])
+        } else {
+            None
+        };
+        write_details(&mut out, state.orb_types[id], state.charges[id], state.healths[id], of);
+        out.push('\n');
+    }
+
+    //empty slots only need writing out if something comes after them
+    let used_slots = (0..INVENTORY_SIZE)
+        .rev()
+        .find(|&i| !state.inventory.get(i).is_empty())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    for i in 0..used_slots {
+        let item = state.inventory.get(i);
+
+        if item.is_empty() {
+            out.push_str("inventory empty\n");
+            continue;
+        }
+
+        out.push_str(&format!(
+            "inventory {} {}",
+            orb_type_name(item.orb_type),
+            aspect_name(item.aspect)
+        ));
+
+        let of = if item.constituents.as_slice().is_empty() {
+            None
+        } else {
+            Some(&item.constituents)
+        };
+        write_details(&mut out, item.orb_type, item.charge, item.health, of);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn without_comments(text: &str) -> String {
+        text.lines()
+            .filter(|line| !line.starts_with(';') && !line.trim().is_empty())
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    #[test]
+    fn bundled_levels_round_trip() {
+        for &level in BUNDLED_LEVELS.iter() {
+            let state = parse(level).unwrap_or_else(|error| panic!("{}", error));
+
+            assert_eq!(export(&state), without_comments(level));
+        }
+    }
+
+    #[test]
+    fn exported_details_round_trip() {
+        let mut state = GameState::new();
+        let id = (0..GameState::ENTITY_COUNT)
+            .find(|&id| state.is_orb(id))
+            .unwrap();
+        state.set_charge(id, 5);
+        state.healths[id] = 1;
+        state.inventory.remove(0);
+        state.chained_pushes = true;
+
+        let exported = export(&state);
+
+        let reparsed = parse(&exported).unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(export(&reparsed), exported);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        let error = parse("aspects 3\navatar 0 0\norb dead mauve 1 1 four 0 0\n").err().unwrap();
+
+        assert_eq!(
+            error,
+            ParseError {
+                line: 3,
+                column: 10,
+                kind: ParseErrorKind::UnknownAspect("mauve".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn aspects_past_the_aspect_count_are_rejected() {
+        let error = parse("aspects 2\navatar 0 0\norb dead black 1 1 four 0 0\n").err().unwrap();
+        assert_eq!((error.line, error.column), (3, 10));
+        assert_eq!(error.kind, ParseErrorKind::OutOfRange("aspect".to_string()));
+
+        let error = parse("aspects 2\ninventory blob red of live-red live-black\n").err().unwrap();
+        assert_eq!((error.line, error.column), (2, 32));
+
+        let error = parse("inventory dead red\naspects 2\n").err().unwrap();
+        assert_eq!(error.kind, ParseErrorKind::AspectsAfterOrbs);
+    }
+
+    #[test]
+    fn short_terrain_rows_are_rejected() {
+        let error = parse("terrain\n......\n....\n").err().unwrap();
+
+        assert_eq!(error.line, 3);
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::WrongRowLength {
+                expected: DEFAULT_BOARD_WIDTH as usize,
+                found: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn other_board_sizes_round_trip() {
+        let level = "aspects 3\nboard 3 2\nterrain\n.#.\n~.+\navatar 2 1\n";
+        let state = parse(level).unwrap_or_else(|error| panic!("{}", error));
+
+        assert_eq!(state.board, Board { width: 3, height: 2 });
+        assert_eq!(export(&state), level);
+    }
+
+    #[test]
+    fn positions_are_checked_against_the_board_size() {
+        let error = parse("board 3 2\navatar 3 0\n").err().unwrap();
+
+        assert_eq!((error.line, error.column), (2, 8));
+        assert_eq!(error.kind, ParseErrorKind::OutOfRange("x".to_string()));
+    }
+
+    #[test]
+    fn missing_values_point_past_the_end_of_the_line() {
+        let error = parse("avatar 3").err().unwrap();
+
+        assert_eq!((error.line, error.column), (1, 9));
+        assert_eq!(error.kind, ParseErrorKind::MissingValue);
+    }
+}
@@ -2,6 +2,8 @@ pub mod rendering;
 pub use rendering::Framebuffer;
 pub use rendering::Position;
 pub use rendering::Appearance;
+pub use rendering::{cell_x_to_px_x, cell_y_to_px_y, intra_px, slide_offset};
+pub use rendering::throw_connects;
 
 pub use rendering::IntraCellPosition::{self, Four, Nine};
 pub use rendering::{_2by2, _3by3};
@@ -11,115 +13,100 @@ pub use rendering::Shape;
 pub mod constants;
 pub use constants::*;
 
+pub mod pattern;
+pub use pattern::Pattern;
+
+pub mod terrain;
+pub use terrain::Terrain;
+
+pub mod inventory;
+pub use inventory::{Inventory, InventoryItem};
+
+pub mod history;
+pub use history::History;
+
+pub mod level;
+
+pub mod rng;
+pub use rng::Rng;
+
+pub mod generator;
+
+#[derive(Clone)]
 pub struct GameState {
     pub entities: [Component::Ty; GameState::ENTITY_COUNT],
 
     pub positions: [Position; GameState::ENTITY_COUNT],
     pub appearances: [Appearance; GameState::ENTITY_COUNT],
+    pub aspects: [Aspect; GameState::ENTITY_COUNT],
     pub intra_cell_positions: [IntraCellPosition; GameState::ENTITY_COUNT],
     pub player_types: [PlayerType; GameState::ENTITY_COUNT],
+    pub orb_types: [OrbType; GameState::ENTITY_COUNT],
+    pub charges: [u8; GameState::ENTITY_COUNT],
+    pub constituents: [Constituents; GameState::ENTITY_COUNT],
+    pub healths: [u8; GameState::ENTITY_COUNT],
+
+    pub board: Board,
+    //one per cell of `board`, in index order
+    pub terrain: Vec<Terrain>,
 
+    pub beams: Vec<Beam>,
+
+    pub scene: Scene,
     pub mode: Mode,
 
-    pub inventory: [OrbType; 3],
-    pub inventory_index: u8,
+    //world turns pass when the avatar acts, once everything has finished moving
+    pub turn: u32,
+    pub turn_pending: bool,
+
+    pub show_danger_map: bool,
+    //whether pushing an orb into a cell of pushable orbs pushes those along too
+    pub chained_pushes: bool,
+
+    //how many of the aspects in `ASPECT_COLOURS` are in play
+    pub aspect_count: u8,
+
+    pub inventory: Inventory,
 
     //TODO Depending on how much I have to do things like this,
     //consider having multiple ways to retrieve compoents effiecently,
     //e.g. by compoent type, position etc.
     pub avatarId: usize,
     pub selectrixId: usize,
+
+    pub history: History,
+
+    //`None` for boards that weren't generated
+    pub seed: Option<u32>,
+    pub rng: Rng,
 }
 
 impl GameState {
     pub const ENTITY_COUNT: usize = 256;
 
     pub fn new() -> GameState {
+        let mut state = level::parse(level::BUNDLED_LEVELS[0])
+            .expect("the bundled levels are checked by tests");
+        state.scene = Scene::Title;
+
+        state
+    }
+
+    //Just the avatar and the selectrix, on a plain floor.
+    pub fn blank() -> GameState {
         let mut entities = [Component::Ty::empty(); GameState::ENTITY_COUNT];
-        let mut positions = [(0, 0); GameState::ENTITY_COUNT];
         let mut appearances = [Appearance::default(); GameState::ENTITY_COUNT];
-        let mut intra_cell_positions = [Four(_2by2::_0_0); GameState::ENTITY_COUNT];
         let mut player_types = [PlayerType::default(); GameState::ENTITY_COUNT];
 
-        {
-            let mut i = 0;
-            while let Some(pos) = get_board_xy(i) {
-                entities[i].insert(Component::Position | Component::Appearance);
-                positions[i] = pos;
-                appearances[i].colour = FLOOR;
-
-                i += 1;
-            }
-        }
-
-        let avatarId = BOARD_LENGTH;
+        let avatarId = 0;
 
         entities[avatarId] |=
             Component::PlayerControlled | Component::Position | Component::Appearance;
-        positions[avatarId] = get_board_xy(avatarId).unwrap_or((0, 0));
         appearances[avatarId].colour = BLUE;
         appearances[avatarId].shape = Shape::Player;
         player_types[avatarId] = PlayerType::Avatar;
 
-        let nineCircleIdBase = avatarId + 1;
-
-        for circleId in nineCircleIdBase..nineCircleIdBase + 9 {
-            entities[circleId] |=
-                Component::Position | Component::Appearance | Component::IntraCellPosition;
-            positions[circleId] = (3, 4);
-            appearances[circleId].colour = RED;
-            appearances[circleId].shape = Shape::DeadOrb0;
-            intra_cell_positions[circleId] = match circleId - nineCircleIdBase {
-                0 => Nine(_3by3::_0_0),
-                1 => Nine(_3by3::_0_1),
-                2 => Nine(_3by3::_0_2),
-                3 => Nine(_3by3::_1_0),
-                4 => Nine(_3by3::_1_1),
-                5 => Nine(_3by3::_1_2),
-                6 => Nine(_3by3::_2_0),
-                7 => Nine(_3by3::_2_1),
-                _ => Nine(_3by3::_2_2),
-            };
-        }
-
-        let fourCircleIdBase = nineCircleIdBase + 9;
-
-        for circleId in fourCircleIdBase..fourCircleIdBase + 4 {
-            entities[circleId] |=
-                Component::Position | Component::Appearance | Component::IntraCellPosition;
-            positions[circleId] = (4, 3);
-            appearances[circleId].colour = RED;
-            appearances[circleId].shape = Shape::Blob0;
-            intra_cell_positions[circleId] = match circleId - fourCircleIdBase {
-                0 => Four(_2by2::_0_0),
-                1 => Four(_2by2::_1_0),
-                2 => Four(_2by2::_0_1),
-                _ => Four(_2by2::_1_1),
-            };
-        }
-
-        let solidNineCircleIdBase = fourCircleIdBase + 4;
-
-        for circleId in solidNineCircleIdBase..solidNineCircleIdBase + 9 {
-            entities[circleId] |=
-                Component::Position | Component::Appearance | Component::IntraCellPosition;
-            positions[circleId] = (3, 3);
-            appearances[circleId].colour = RED;
-            appearances[circleId].shape = Shape::LiveOrb0;
-            intra_cell_positions[circleId] = match circleId - solidNineCircleIdBase {
-                0 => Nine(_3by3::_0_0),
-                1 => Nine(_3by3::_0_1),
-                2 => Nine(_3by3::_0_2),
-                3 => Nine(_3by3::_1_0),
-                4 => Nine(_3by3::_1_1),
-                5 => Nine(_3by3::_1_2),
-                6 => Nine(_3by3::_2_0),
-                7 => Nine(_3by3::_2_1),
-                _ => Nine(_3by3::_2_2),
-            };
-        }
-
-        let selectrixId = solidNineCircleIdBase + 9;
+        let selectrixId = avatarId + 1;
 
         entities[selectrixId] |= Component::Position | Component::Appearance
             | Component::PlayerControlled
@@ -130,17 +117,126 @@ impl GameState {
 
         GameState {
             entities,
-            positions,
+            positions: [(0, 0); GameState::ENTITY_COUNT],
             appearances,
-            intra_cell_positions,
+            aspects: [Aspect::default(); GameState::ENTITY_COUNT],
+            intra_cell_positions: [Four(_2by2::_0_0); GameState::ENTITY_COUNT],
             player_types,
+            orb_types: [OrbType::default(); GameState::ENTITY_COUNT],
+            charges: [0; GameState::ENTITY_COUNT],
+            constituents: [Constituents::default(); GameState::ENTITY_COUNT],
+            healths: [0; GameState::ENTITY_COUNT],
+            board: Board::default(),
+            terrain: vec![Terrain::Floor; Board::default().length()],
+            beams: Vec::new(),
+            scene: Scene::Playing,
             mode: Mode::MoveAvatar,
+            turn: 0,
+            turn_pending: false,
+            show_danger_map: false,
+            chained_pushes: false,
+            aspect_count: DEFAULT_ASPECT_COUNT,
             avatarId,
             selectrixId,
-            inventory: [OrbType::DeadOrb, OrbType::DeadOrb, OrbType::NoOrb], //Default::default(),
-            inventory_index: 0,
+            inventory: Inventory::default(),
+            history: History::default(),
+            seed: None,
+            rng: Rng::new(0),
         }
     }
+
+    pub fn add_entity(&mut self) -> Option<usize> {
+        let id = self.entities.iter().position(|e| e.is_empty())?;
+
+        self.positions[id] = (0, 0);
+        self.appearances[id] = Appearance::default();
+        self.aspects[id] = Aspect::default();
+        self.intra_cell_positions[id] = Four(_2by2::_0_0);
+        self.player_types[id] = PlayerType::default();
+        self.orb_types[id] = OrbType::default();
+        self.charges[id] = 0;
+        self.constituents[id] = Constituents::default();
+        self.healths[id] = 0;
+
+        Some(id)
+    }
+
+    pub fn remove_entity(&mut self, id: usize) {
+        self.entities[id] = Component::Ty::empty();
+    }
+
+    pub fn add_orb(
+        &mut self,
+        orb_type: OrbType,
+        pos: Position,
+        intra_pos: IntraCellPosition,
+        aspect: Aspect,
+    ) -> Option<usize> {
+        let shape = orb_type.shape()?;
+        let id = self.add_entity()?;
+
+        self.entities[id] = Component::Position | Component::Appearance
+            | Component::IntraCellPosition | Component::Orb
+            | Component::Charge | Component::Health;
+        self.healths[id] = MAX_HEALTH;
+        self.positions[id] = pos;
+        self.intra_cell_positions[id] = intra_pos;
+        self.aspects[id] = aspect;
+        self.appearances[id].colour = aspect.colour();
+        self.appearances[id].shape = shape;
+        self.orb_types[id] = orb_type;
+        self.set_charge(
+            id,
+            if orb_type == OrbType::LiveOrb {
+                FULL_CHARGE
+            } else {
+                0
+            },
+        );
+
+        Some(id)
+    }
+
+    pub fn set_charge(&mut self, id: usize, charge: u8) {
+        self.charges[id] = charge;
+        self.appearances[id].charge = charge;
+    }
+
+    //An orb that isn't made of other orbs is its own sole constituent.
+    pub fn constituents_of(&self, id: usize) -> Constituents {
+        if self.entities[id].contains(Component::Constituents) {
+            self.constituents[id]
+        } else {
+            let mut result = Constituents::default();
+            result.push(Constituent {
+                orb_type: self.orb_types[id],
+                aspect: self.aspects[id],
+            });
+            result
+        }
+    }
+
+    //Clears the terrain too, since the old layout won't line up.
+    pub fn set_board(&mut self, board: Board) {
+        self.board = board;
+        self.terrain = vec![Terrain::Floor; board.length()];
+    }
+
+    //Off the board counts as a wall.
+    pub fn terrain_at(&self, (x, y): Position) -> Terrain {
+        self.board
+            .index(x, y)
+            .map(|i| self.terrain[i])
+            .unwrap_or(Terrain::Wall)
+    }
+
+    pub fn all_aspects_mask(&self) -> u32 {
+        (1 << self.aspect_count) - 1
+    }
+
+    pub fn is_orb(&self, id: usize) -> bool {
+        self.entities[id].contains(Component::Orb | Component::Position)
+    }
 }
 
 pub mod Component {
@@ -153,6 +249,11 @@ pub mod Component {
              | Appearance.bits
              | PlayerControlled.bits,
              const IntraCellPosition = 1 << 3,
+             const Orb = 1 << 4,
+             const Charge = 1 << 5,
+             const Constituents = 1 << 6,
+             const Thrown = 1 << 7,
+             const Health = 1 << 8,
         }
     }
 }
@@ -162,6 +263,7 @@ pub enum PlayerType {
     NonPlayer,
     Avatar,
     Selectrix,
+    Ally,
 }
 
 impl Default for PlayerType {
@@ -174,6 +276,38 @@ impl Default for PlayerType {
 pub enum OrbType {
     NoOrb,
     DeadOrb,
+    LiveOrb,
+    Blob,
+    CreatedOrb,
+}
+
+impl OrbType {
+    pub fn shape(&self) -> Option<Shape> {
+        match *self {
+            OrbType::NoOrb => None,
+            OrbType::DeadOrb => Some(Shape::DeadOrb0),
+            OrbType::LiveOrb => Some(Shape::LiveOrb0),
+            OrbType::Blob => Some(Shape::Blob0),
+            OrbType::CreatedOrb => Some(Shape::CreatedOrb0),
+        }
+    }
+
+    pub const STAGE_COUNT: usize = 4;
+
+    //Which column of the table in Design.md this is. `NoOrb` isn't in the table.
+    pub fn stage_index(&self) -> Option<usize> {
+        match *self {
+            OrbType::NoOrb => None,
+            OrbType::DeadOrb => Some(0),
+            OrbType::LiveOrb => Some(1),
+            OrbType::Blob => Some(2),
+            OrbType::CreatedOrb => Some(3),
+        }
+    }
+
+    pub fn can_be_carried(&self) -> bool {
+        matches!(*self, OrbType::DeadOrb | OrbType::LiveOrb)
+    }
 }
 
 impl Default for OrbType {
@@ -182,12 +316,168 @@ impl Default for OrbType {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Aspect(pub u8);
+
+impl Aspect {
+    pub const Red: Aspect = Aspect(0);
+    pub const White: Aspect = Aspect(1);
+    pub const Black: Aspect = Aspect(2);
+
+    pub fn colour(&self) -> u32 {
+        ASPECT_COLOURS[self.0 as usize % ASPECT_COLOURS.len()]
+    }
+}
+
+pub const MAX_CONSTITUENTS: usize = 16;
+
+#[derive(Clone, Copy, Default)]
+pub struct Constituent {
+    pub orb_type: OrbType,
+    pub aspect: Aspect,
+}
+
+//The orbs a combined entity like a blob was made from, so it can be split up again later.
+#[derive(Clone, Copy, Default)]
+pub struct Constituents {
+    orbs: [Constituent; MAX_CONSTITUENTS],
+    len: u8,
+}
+
+impl Constituents {
+    pub fn push(&mut self, constituent: Constituent) -> bool {
+        let len = self.len as usize;
+        if len >= MAX_CONSTITUENTS {
+            return false;
+        }
+
+        self.orbs[len] = constituent;
+        self.len += 1;
+
+        true
+    }
+
+    pub fn extend(&mut self, other: &Constituents) {
+        for &constituent in other.as_slice() {
+            self.push(constituent);
+        }
+    }
+
+    //bit n is set if `Aspect(n)` is in here
+    pub fn aspect_mask(&self) -> u32 {
+        self.as_slice()
+            .iter()
+            .fold(0, |mask, constituent| mask | 1 << constituent.aspect.0)
+    }
+
+    pub fn as_slice(&self) -> &[Constituent] {
+        &self.orbs[..self.len as usize]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn delta(&self) -> (isize, isize) {
+        match *self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    //Along whichever axis `to` is furthest from `from` on, `None` if they are the same cell.
+    pub fn towards((from_x, from_y): Position, (to_x, to_y): Position) -> Option<Direction> {
+        let (dx, dy) = (to_x as isize - from_x as isize, to_y as isize - from_y as isize);
+
+        if dx == 0 && dy == 0 {
+            None
+        } else if dx.abs() >= dy.abs() {
+            Some(if dx < 0 { Direction::Left } else { Direction::Right })
+        } else {
+            Some(if dy < 0 { Direction::Up } else { Direction::Down })
+        }
+    }
+
+    //Moves one slot over, crossing into the next cell at the edges.
+    //`None` if that would be off the board.
+    pub fn step_intra(
+        &self,
+        board: Board,
+        pos: Position,
+        intra_pos: IntraCellPosition,
+    ) -> Option<(Position, IntraCellPosition)> {
+        let (on_edge, new_intra_pos) = match *self {
+            Direction::Up => (intra_pos.on_top_edge(), intra_pos.up()),
+            Direction::Down => (intra_pos.on_bottom_edge(), intra_pos.down()),
+            Direction::Left => (intra_pos.on_left_edge(), intra_pos.left()),
+            Direction::Right => (intra_pos.on_right_edge(), intra_pos.right()),
+        };
+
+        if on_edge {
+            Some((self.step(board, pos)?, new_intra_pos))
+        } else {
+            Some((pos, new_intra_pos))
+        }
+    }
+
+    //`None` if that would be off the board
+    pub fn step(&self, board: Board, (x, y): Position) -> Option<Position> {
+        let result = match *self {
+            Direction::Up => (x, y.checked_sub(1)?),
+            Direction::Down => (x, y.checked_add(1)?),
+            Direction::Left => (x.checked_sub(1)?, y),
+            Direction::Right => (x.checked_add(1)?, y),
+        };
+
+        if board.contains(result.0, result.1) {
+            Some(result)
+        } else {
+            None
+        }
+    }
+}
+
+//A line drawn for a few frames, like a healing ray. In pixels.
+#[derive(Clone, Copy)]
+pub struct Beam {
+    pub from: (isize, isize),
+    pub to: (isize, isize),
+    pub colour: u32,
+    pub frames_left: u8,
+}
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub enum Mode {
     MoveAvatar,
     MoveSelectrix,
 }
 
+//Which screen is showing. Start moves between them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scene {
+    Title,
+    Playing,
+    Paused,
+    LevelComplete,
+    //an aspect has been lost, so the level can't be won any more
+    GameOver,
+}
+
 impl Default for Mode {
     fn default() -> Self {
         Mode::MoveAvatar
@@ -247,76 +537,172 @@ pub mod Button {
     }
 }
 
-#[allow(dead_code)]
-pub fn get_board_index(x: BoardCoord, y: BoardCoord) -> Option<usize> {
-    if !xy_on_board(x, y) {
-        return None;
+//The cells a straight line from one cell to another passes through, in order, including both
+//ends. See http://members.chello.at/easyfilter/bresenham.html
+pub fn cells_between(from: Position, to: Position) -> Vec<Position> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (x1, y1) = (to.0 as isize, to.1 as isize);
+
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    let mut result = Vec::with_capacity((dx - dy + 1) as usize);
+    loop {
+        result.push((x as BoardCoord, y as BoardCoord));
+
+        if x == x1 && y == y1 {
+            break;
+        }
+
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
     }
 
-    let result = (y.saturating_mul(BOARD_WIDTH).saturating_add(x)) as usize;
+    result
+}
+
+//The centre of the slot, in twelfths of a cell, across the whole board.
+pub fn board_twelfths((x, y): Position, intra_pos: IntraCellPosition) -> (isize, isize) {
+    let (left, top, right, bottom) = intra_pos.sixths_rect();
+
+    (
+        x as isize * 12 + (left + right) as isize,
+        y as isize * 12 + (top + bottom) as isize,
+    )
+}
+
+//The size of the board, in cells, and where things go on the screen because of it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Board {
+    pub width: BoardCoord,
+    pub height: BoardCoord,
+}
 
-    if is_index_on_board(result) {
-        Some(result)
-    } else {
-        None
+impl Default for Board {
+    fn default() -> Self {
+        Board {
+            width: DEFAULT_BOARD_WIDTH,
+            height: DEFAULT_BOARD_HEIGHT,
+        }
     }
 }
 
-pub fn get_board_xy(index: usize) -> Option<(BoardCoord, BoardCoord)> {
-    if !is_index_on_board(index) {
-        return None;
+impl Board {
+    //`None` if it wouldn't fit on the screen, or would have no cells.
+    pub fn new(width: BoardCoord, height: BoardCoord) -> Option<Board> {
+        if width == 0 || height == 0 || width > MAX_BOARD_WIDTH || height > MAX_BOARD_HEIGHT {
+            None
+        } else {
+            Some(Board { width, height })
+        }
     }
 
-    let result = (
-        index as BoardCoord % BOARD_WIDTH,
-        index as BoardCoord / BOARD_WIDTH,
-    );
+    pub fn length(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
 
-    if xy_on_board(result.0, result.1) {
-        Some(result)
-    } else {
-        None
+    pub fn contains(&self, x: BoardCoord, y: BoardCoord) -> bool {
+        x < self.width && y < self.height
     }
-}
 
-#[cfg(test)]
-#[macro_use]
-extern crate quickcheck;
+    pub fn index(&self, x: BoardCoord, y: BoardCoord) -> Option<usize> {
+        if self.contains(x, y) {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn xy(&self, index: usize) -> Option<Position> {
+        if index < self.length() {
+            Some((
+                (index % self.width as usize) as BoardCoord,
+                (index / self.width as usize) as BoardCoord,
+            ))
+        } else {
+            None
+        }
+    }
+
+    //every cell, in index order
+    pub fn cells(&self) -> Vec<Position> {
+        (0..self.length()).filter_map(|i| self.xy(i)).collect()
+    }
+
+    //in pixels, with a spacer pixel after the last cell
+    pub fn hud_left_edge(&self) -> usize {
+        cell_x_to_px_x(self.width as usize) + 1
+    }
+
+    pub fn hud_width(&self) -> usize {
+        SCREEN_WIDTH - self.hud_left_edge()
+    }
+
+    pub fn inventory_width(&self) -> usize {
+        (self.hud_width() / 3).min(MAX_INVENTORY_WIDTH)
+    }
+
+    //centred in the HUD
+    pub fn inventory_left_edge(&self) -> usize {
+        self.hud_left_edge() + (self.hud_width() - self.inventory_width()) / 2
+    }
+}
 
 #[cfg(test)]
 mod board_indices {
-    use ::*;
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+
+    impl Arbitrary for Board {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let widths: Vec<BoardCoord> = (1..=MAX_BOARD_WIDTH).collect();
+            let heights: Vec<BoardCoord> = (1..=MAX_BOARD_HEIGHT).collect();
+
+            Board {
+                width: *g.choose(&widths).unwrap(),
+                height: *g.choose(&heights).unwrap(),
+            }
+        }
+    }
 
     quickcheck! {
-        fn i_xy_i(i: usize) -> bool {
-              let expected = if is_index_on_board(i) {
+        fn i_xy_i(board: Board, i: usize) -> bool {
+              let expected = if i < board.length() {
                   Some(i)
               } else {
                   None
               };
 
-              expected == get_board_xy(i).and_then(|(x,y)| get_board_index(x,y))
+              expected == board.xy(i).and_then(|(x,y)| board.index(x,y))
         }
     }
 
     quickcheck! {
-        fn xy_i_xy(x: BoardCoord, y: BoardCoord) -> bool {
-             let expected = if xy_on_board(x, y) {
+        fn xy_i_xy(board: Board, x: BoardCoord, y: BoardCoord) -> bool {
+             let expected = if board.contains(x, y) {
                  Some((x, y))
              } else {
                  None
              };
 
-             expected == get_board_index(x,y).and_then(|i| get_board_xy(i))
+             expected == board.index(x,y).and_then(|i| board.xy(i))
         }
     }
 
-}
-
-fn is_index_on_board(piece_index: usize) -> bool {
-    piece_index < BOARD_LENGTH
-}
-
-fn xy_on_board(x: BoardCoord, y: BoardCoord) -> bool {
-    x < BOARD_WIDTH && y < BOARD_HEIGHT
+    quickcheck! {
+        fn the_hud_always_fits(board: Board) -> bool {
+            board.hud_width() >= MIN_HUD_WIDTH
+                && cell_y_to_px_y(board.height as usize) <= SCREEN_HEIGHT
+        }
+    }
 }
@@ -0,0 +1,106 @@
+use super::*;
+
+//A set of slots on the board for orbs to arrange themselves into.
+pub struct Pattern {
+    pub slots: Vec<(Position, _3by3)>,
+}
+
+impl Pattern {
+    //Slots in pairs that mirror each other through the centre of the board, starting from the
+    //middle and working outwards. A pair is skipped if `allowed` rejects either half of it, so
+    //the result is symmetrical regardless.
+    pub fn symmetric<F>(board: Board, count: usize, allowed: F) -> Pattern
+    where
+        F: Fn(Position, _3by3) -> bool,
+    {
+        let width = board.width as isize * 3;
+        let height = board.height as isize * 3;
+
+        let mut candidates = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                //twice the distance, so the centre, which is between slots, is a whole number
+                let distance = (2 * x - (width - 1)).abs().max((2 * y - (height - 1)).abs());
+                candidates.push((distance, y, x));
+            }
+        }
+        candidates.sort();
+
+        let mut slots = Vec::with_capacity(count + 1);
+        for (_, y, x) in candidates {
+            if slots.len() >= count {
+                break;
+            }
+
+            let slot = slot_at(x, y);
+            let mirrored = slot_at(width - 1 - x, height - 1 - y);
+
+            if slots.contains(&slot) || !allowed(slot.0, slot.1) || !allowed(mirrored.0, mirrored.1)
+            {
+                continue;
+            }
+
+            slots.push(slot);
+            if mirrored != slot {
+                slots.push(mirrored);
+            }
+        }
+
+        Pattern { slots }
+    }
+
+    //Greedily gives each point, in order, the nearest slot nobody has been given yet.
+    //Returns indices into `slots`, `None` if they ran out.
+    pub fn assign(&self, points: &[(isize, isize)]) -> Vec<Option<usize>> {
+        let mut taken = vec![false; self.slots.len()];
+
+        points
+            .iter()
+            .map(|&(x, y)| {
+                let nearest = (0..self.slots.len())
+                    .filter(|&i| !taken[i])
+                    .min_by_key(|&i| {
+                        let (slot_x, slot_y) = self.twelfths(i);
+                        ((slot_x - x).abs() + (slot_y - y).abs(), i)
+                    });
+
+                if let Some(i) = nearest {
+                    taken[i] = true;
+                }
+
+                nearest
+            })
+            .collect()
+    }
+
+    pub fn twelfths(&self, index: usize) -> (isize, isize) {
+        let (pos, slot) = self.slots[index];
+
+        board_twelfths(pos, Nine(slot))
+    }
+}
+
+fn slot_at(x: isize, y: isize) -> (Position, _3by3) {
+    (
+        ((x / 3) as BoardCoord, (y / 3) as BoardCoord),
+        _3by3::from_xy((x % 3) as u8, (y % 3) as u8),
+    )
+}
+
+#[cfg(test)]
+mod symmetric_patterns {
+    use super::*;
+
+    quickcheck! {
+        fn every_slot_is_mirrored(board: Board, count: u8) -> bool {
+            let pattern = Pattern::symmetric(board, count as usize, |_, _| true);
+            let (width, height) = (board.width as isize * 12, board.height as isize * 12);
+
+            (0..pattern.slots.len()).all(|i| {
+                let (x, y) = pattern.twelfths(i);
+
+                (0..pattern.slots.len()).any(|j| pattern.twelfths(j) == (width - x, height - y))
+            })
+        }
+    }
+}
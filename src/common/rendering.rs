@@ -48,6 +48,20 @@ macro_rules! set_alpha {
     };
 }
 
+//Each row of a digit is the low `DIGIT_WIDTH` bits, leftmost pixel first.
+const DIGIT_FONT: [[u8; DIGIT_HEIGHT]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
 //TODO either pick nice numbers (multiples of 4?) that don't look bad due to rounding,
 //or switch to floats and do our own bi-linear blending down to integer pixels
 impl Framebuffer {
@@ -80,6 +94,24 @@ impl Framebuffer {
         }
     }
 
+    //Draws `n` in decimal with `DIGIT_FONT`, with its top left corner at `x`, `y`.
+    pub fn draw_number(&mut self, x: usize, y: usize, n: u32, colour: u32) {
+        let digits = n.to_string();
+
+        for (i, digit) in digits.bytes().enumerate() {
+            let glyph = DIGIT_FONT[(digit - b'0') as usize];
+            let left = x + i * (DIGIT_WIDTH + 1);
+
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..DIGIT_WIDTH {
+                    if bits & (1 << (DIGIT_WIDTH - 1 - column)) != 0 {
+                        self.draw_filled_rect(left + column, y + row, 1, 1, colour);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn draw_rect(&mut self, x: usize, y: usize, width: usize, height: usize, colour: u32) {
         let one_past_right_edge = x + width;
         let one_past_bottom_edge = y + height;
@@ -117,6 +149,77 @@ impl Framebuffer {
         }
     }
 
+    pub fn blend_filled_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        colour: u32,
+    ) {
+        for current_y in y..y + height {
+            for current_x in x..x + width {
+                let i = Framebuffer::xy_to_i(current_x, current_y);
+                if i < self.buffer.len() {
+                    self.blend(i, colour);
+                }
+            }
+        }
+    }
+
+    pub fn draw_line(&mut self, from: (isize, isize), to: (isize, isize), colour: u32) {
+        self.draw_line_every(from, to, colour, 1);
+    }
+
+    //For things that might happen, rather than things that are there.
+    pub fn draw_dotted_line(&mut self, from: (isize, isize), to: (isize, isize), colour: u32) {
+        self.draw_line_every(from, to, colour, 3);
+    }
+
+    //Draws every `spacing`th pixel of the line.
+    //see http://members.chello.at/easyfilter/bresenham.html
+    fn draw_line_every(
+        &mut self,
+        from: (isize, isize),
+        to: (isize, isize),
+        colour: u32,
+        spacing: usize,
+    ) {
+        let (mut x, mut y) = from;
+        let (x1, y1) = to;
+
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let sx = if x < x1 { 1 } else { -1 };
+        let sy = if y < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let mut step = 0;
+
+        loop {
+            if step % spacing == 0 && x >= 0 && y >= 0 && (x as usize) < SCREEN_WIDTH {
+                let i = Framebuffer::xy_to_i(x as usize, y as usize);
+                if i < self.buffer.len() {
+                    self.buffer[i] = colour;
+                }
+            }
+
+            if x == x1 && y == y1 {
+                break;
+            }
+            step += 1;
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
     pub fn clear(&mut self) {
         for i in 0..self.buffer.len() {
             self.buffer[i] = 0;
@@ -402,6 +505,8 @@ pub struct Appearance {
     pub colour: u32,
     pub shape: Shape,
     pub offset: (isize, isize),
+    //out of FULL_CHARGE
+    pub charge: u8,
 }
 
 pub fn offset_by(value: usize, offset: isize) -> usize {
@@ -412,6 +517,28 @@ pub fn offset_by(value: usize, offset: isize) -> usize {
     }
 }
 
+pub fn intra_px(pos: Position, intra_pos: IntraCellPosition) -> (isize, isize) {
+    let (x, y) = pos;
+    let (intra_x, intra_y) = intra_pos.get_offset();
+
+    (
+        cell_x_to_px_x(x as usize) as isize + intra_x,
+        cell_y_to_px_y(y as usize) as isize + intra_y,
+    )
+}
+
+//The `Appearance::offset` that makes something moved from `from` to `to` appear to still be
+//at `from`, so that reducing the offset slides it over.
+pub fn slide_offset(
+    from: (Position, IntraCellPosition),
+    to: (Position, IntraCellPosition),
+) -> (isize, isize) {
+    let (from_x, from_y) = intra_px(from.0, from.1);
+    let (to_x, to_y) = intra_px(to.0, to.1);
+
+    (from_x - to_x, from_y - to_y)
+}
+
 impl Appearance {
     pub fn render_intra_positioned(
         &self,
@@ -441,6 +568,11 @@ impl Appearance {
         let px_x = offset_by(cell_x_to_px_x(x as usize), x_off);
         let px_y = offset_by(cell_y_to_px_y(y as usize), y_off);
 
+        self.render_at_px(framebuffer, px_x, px_y);
+    }
+
+    //Orbs are drawn centred on the point, everything else from its top left corner.
+    pub fn render_at_px(&self, framebuffer: &mut Framebuffer, px_x: usize, px_y: usize) {
         let colour = self.colour;
 
         match self.shape {
@@ -458,6 +590,13 @@ impl Appearance {
             }
             Shape::DeadOrb0 => {
                 framebuffer.draw_circle(px_x, px_y, ORB_RADIUS, colour);
+
+                //the orb fills in from the middle as it recharges
+                let charge_radius =
+                    (ORB_RADIUS - 1) * self.charge as usize / FULL_CHARGE as usize;
+                if charge_radius > 0 {
+                    framebuffer.draw_filled_circle(px_x, px_y, charge_radius, colour);
+                }
             }
             Shape::LiveOrb0 => {
                 framebuffer.draw_filled_circle(px_x, px_y, ORB_RADIUS, colour);
@@ -476,6 +615,11 @@ impl Appearance {
                     colour,
                 );
             }
+            Shape::CreatedOrb0 => {
+                for ring in 0..3 {
+                    framebuffer.draw_circle(px_x, px_y, ORB_RADIUS + ring * 2, colour);
+                }
+            }
             Shape::Selectrix => {
                 framebuffer.draw_rect(
                     px_x - CELL_WIDTH / 4,
@@ -497,18 +641,19 @@ impl Appearance {
     pub fn reduce_offset(&mut self, offset: isize) {
         let (x_off, y_off) = (&mut self.offset.0, &mut self.offset.1);
 
+        //clamp so offsets that aren't a multiple of `offset` still reach zero
         if *x_off > 0 {
-            *x_off -= offset;
+            *x_off = (*x_off - offset).max(0);
         } else if *x_off < 0 {
-            *x_off += offset;
+            *x_off = (*x_off + offset).min(0);
         } else {
             //do nothing
         }
 
         if *y_off > 0 {
-            *y_off -= offset;
+            *y_off = (*y_off - offset).max(0);
         } else if *y_off < 0 {
-            *y_off += offset;
+            *y_off = (*y_off + offset).min(0);
         } else {
             //do nothing
         }
@@ -522,6 +667,7 @@ pub enum Shape {
     DeadOrb0,
     LiveOrb0,
     Blob0,
+    CreatedOrb0,
     Selectrix,
 }
 
@@ -533,7 +679,7 @@ impl Default for Shape {
 
 pub type Position = (BoardCoord, BoardCoord);
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum _2by2 {
     _0_0,
     _0_1,
@@ -542,6 +688,17 @@ pub enum _2by2 {
 }
 
 impl _2by2 {
+    pub const ALL: [_2by2; 4] = [_2by2::_0_0, _2by2::_1_0, _2by2::_0_1, _2by2::_1_1];
+
+    pub fn xy(&self) -> (u8, u8) {
+        match *self {
+            _2by2::_0_0 => (0, 0),
+            _2by2::_1_0 => (1, 0),
+            _2by2::_0_1 => (0, 1),
+            _2by2::_1_1 => (1, 1),
+        }
+    }
+
     pub fn right(&self) -> Self {
         match *self {
             _2by2::_0_0 => _2by2::_1_0,
@@ -607,7 +764,7 @@ impl _2by2 {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum _3by3 {
     _0_0,
     _0_1,
@@ -621,6 +778,47 @@ pub enum _3by3 {
 }
 
 impl _3by3 {
+    //coordinates outside the grid are clamped onto it
+    pub fn from_xy(x: u8, y: u8) -> Self {
+        match (x, y) {
+            (0, 0) => _3by3::_0_0,
+            (1, 0) => _3by3::_1_0,
+            (_, 0) => _3by3::_2_0,
+            (0, 1) => _3by3::_0_1,
+            (1, 1) => _3by3::_1_1,
+            (_, 1) => _3by3::_2_1,
+            (0, _) => _3by3::_0_2,
+            (1, _) => _3by3::_1_2,
+            (_, _) => _3by3::_2_2,
+        }
+    }
+
+    pub const ALL: [_3by3; 9] = [
+        _3by3::_0_0,
+        _3by3::_1_0,
+        _3by3::_2_0,
+        _3by3::_0_1,
+        _3by3::_1_1,
+        _3by3::_2_1,
+        _3by3::_0_2,
+        _3by3::_1_2,
+        _3by3::_2_2,
+    ];
+
+    pub fn xy(&self) -> (u8, u8) {
+        match *self {
+            _3by3::_0_0 => (0, 0),
+            _3by3::_1_0 => (1, 0),
+            _3by3::_2_0 => (2, 0),
+            _3by3::_0_1 => (0, 1),
+            _3by3::_1_1 => (1, 1),
+            _3by3::_2_1 => (2, 1),
+            _3by3::_0_2 => (0, 2),
+            _3by3::_1_2 => (1, 2),
+            _3by3::_2_2 => (2, 2),
+        }
+    }
+
     pub fn right(&self) -> Self {
         match *self {
             _3by3::_0_0 => _3by3::_1_0,
@@ -677,6 +875,28 @@ impl _3by3 {
         }
     }
 
+    //Orthogonal neighbours, without wrapping around to the other side of the cell.
+    #[allow(dead_code)]
+    pub fn moves_within_cell(&self) -> Vec<_3by3> {
+        let (x, y) = self.xy();
+        let mut result = Vec::with_capacity(4);
+
+        if x > 0 {
+            result.push(_3by3::from_xy(x - 1, y));
+        }
+        if x < 2 {
+            result.push(_3by3::from_xy(x + 1, y));
+        }
+        if y > 0 {
+            result.push(_3by3::from_xy(x, y - 1));
+        }
+        if y < 2 {
+            result.push(_3by3::from_xy(x, y + 1));
+        }
+
+        result
+    }
+
     pub fn on_left_edge(&self) -> bool {
         match *self {
             _3by3::_0_0 | _3by3::_0_1 | _3by3::_0_2 => true,
@@ -714,7 +934,58 @@ pub enum IntraCellPosition {
 use IntraCellPosition::*;
 
 impl IntraCellPosition {
-    fn get_offset(&self) -> (isize, isize) {
+    //The area covered by the slot, in sixths of a cell, as (left, top, right, bottom).
+    //A sixth is the largest unit both the 2 by 2 and 3 by 3 grids line up with.
+    pub fn sixths_rect(&self) -> (u8, u8, u8, u8) {
+        match *self {
+            Four(pos) => {
+                let (x, y) = pos.xy();
+                (x * 3, y * 3, x * 3 + 3, y * 3 + 3)
+            }
+            Nine(pos) => {
+                let (x, y) = pos.xy();
+                (x * 2, y * 2, x * 2 + 2, y * 2 + 2)
+            }
+        }
+    }
+
+    //in square sixths of a cell
+    pub fn area(&self) -> u8 {
+        let (left, top, right, bottom) = self.sixths_rect();
+
+        (right - left) * (bottom - top)
+    }
+
+    //in square sixths of a cell
+    pub fn overlap_area(&self, other: &IntraCellPosition) -> u8 {
+        let (left, top, right, bottom) = self.sixths_rect();
+        let (other_left, other_top, other_right, other_bottom) = other.sixths_rect();
+
+        let width = right.min(other_right).saturating_sub(left.max(other_left));
+        let height = bottom.min(other_bottom).saturating_sub(top.max(other_top));
+
+        width * height
+    }
+
+    //The 3 by 3 slot in the same corner, for 2 by 2 positions.
+    pub fn to_nine(self) -> IntraCellPosition {
+        match self {
+            Four(pos) => {
+                let (x, y) = pos.xy();
+                Nine(_3by3::from_xy(x * 2, y * 2))
+            }
+            Nine(_) => self,
+        }
+    }
+
+    pub fn overlaps(&self, other: &IntraCellPosition) -> bool {
+        let (left, top, right, bottom) = self.sixths_rect();
+        let (other_left, other_top, other_right, other_bottom) = other.sixths_rect();
+
+        left < other_right && other_left < right && top < other_bottom && other_top < bottom
+    }
+
+    pub fn get_offset(&self) -> (isize, isize) {
         let w = CELL_WIDTH as isize;
         let h = CELL_HEIGHT as isize;
         match *self {
@@ -790,3 +1061,76 @@ intra_pos_passthrough!{
     on_top_edge -> bool,
     on_bottom_edge -> bool,
 }
+
+//A thrown orb connects with an orb if the thrown to area overlaps at least half of the orb.
+//See the "Blob/Orb movement Idea" section of Design.md.
+pub fn throw_connects(thrown_to: _2by2, orb: IntraCellPosition) -> bool {
+    Four(thrown_to).overlap_area(&orb) * 2 >= orb.area()
+}
+
+//The chance, as (hits, out of), that a throw connects with an orb that moves to one of its
+//neighbouring slots within its cell before the throw lands. Nothing wanders around its cell at
+//random yet, so for now this only backs up the odds in Design.md.
+#[allow(dead_code)]
+pub fn hit_chance(thrown_to: _2by2, orb: _3by3) -> (u8, u8) {
+    let moves = orb.moves_within_cell();
+    let hits = moves
+        .iter()
+        .filter(|&&slot| throw_connects(thrown_to, Nine(slot)))
+        .count();
+
+    (hits as u8, moves.len() as u8)
+}
+
+#[cfg(test)]
+mod hit_chances {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen, TestResult};
+
+    impl Arbitrary for _2by2 {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            *g.choose(&_2by2::ALL).unwrap()
+        }
+    }
+
+    impl Arbitrary for _3by3 {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            *g.choose(&_3by3::ALL).unwrap()
+        }
+    }
+
+    fn chance_is(thrown_to: _2by2, orb: _3by3, numerator: u8, denominator: u8) -> bool {
+        let (hits, out_of) = hit_chance(thrown_to, orb);
+
+        hits as u16 * denominator as u16 == numerator as u16 * out_of as u16
+    }
+
+    quickcheck! {
+        fn fully_inside_is_guaranteed(thrown_to: _2by2, orb: _3by3) -> TestResult {
+            if Four(thrown_to).overlap_area(&Nine(orb)) != Nine(orb).area() {
+                return TestResult::discard();
+            }
+
+            TestResult::from_bool(chance_is(thrown_to, orb, 1, 1))
+        }
+    }
+
+    quickcheck! {
+        fn on_a_shared_edge_is_one_in_three(thrown_to: _2by2, orb: _3by3) -> TestResult {
+            let (x, y) = orb.xy();
+            let on_an_edge_between_two = (x == 1) != (y == 1);
+
+            if !on_an_edge_between_two || !Four(thrown_to).overlaps(&Nine(orb)) {
+                return TestResult::discard();
+            }
+
+            TestResult::from_bool(chance_is(thrown_to, orb, 1, 3))
+        }
+    }
+
+    quickcheck! {
+        fn in_the_centre_is_one_in_two(thrown_to: _2by2) -> bool {
+            chance_is(thrown_to, _3by3::_1_1, 1, 2)
+        }
+    }
+}
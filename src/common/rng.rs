@@ -0,0 +1,27 @@
+//A small xorshift generator, so the same seed always gives the same sequence on every platform.
+#[derive(Clone, Copy)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Rng {
+        //xorshift gets stuck on zero, and small seeds would start off looking alike
+        let state = (seed as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+
+        Rng { state }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as u32
+    }
+
+    //A number from 0 up to, but not including, `n`. `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.next_u32() as usize % n
+    }
+}
@@ -0,0 +1,73 @@
+use constants::*;
+use rendering::{cell_x_to_px_x, cell_y_to_px_y, Framebuffer, Position};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Terrain {
+    #[default]
+    Floor,
+    Wall,
+    //swallows any orb that ends up in it
+    Pit,
+    //thrown orbs keep sliding across it
+    Ice,
+    //dead orbs recharge faster on it
+    ChargingPad,
+}
+
+impl Terrain {
+    pub fn blocks_movement(&self) -> bool {
+        *self == Terrain::Wall
+    }
+
+    pub fn swallows_orbs(&self) -> bool {
+        *self == Terrain::Pit
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, (x, y): Position) {
+        let px_x = cell_x_to_px_x(x as usize);
+        let px_y = cell_y_to_px_y(y as usize);
+
+        match *self {
+            Terrain::Floor => {
+                framebuffer.draw_filled_rect(px_x, px_y, CELL_WIDTH, CELL_HEIGHT, FLOOR);
+            }
+            Terrain::Wall => {
+                framebuffer.draw_filled_rect(px_x, px_y, CELL_WIDTH, CELL_HEIGHT, WALL);
+                //a couple of mortar lines
+                framebuffer.draw_filled_rect(px_x, px_y + CELL_HEIGHT / 3, CELL_WIDTH, 1, FLOOR);
+                framebuffer.draw_filled_rect(
+                    px_x,
+                    px_y + CELL_HEIGHT * 2 / 3,
+                    CELL_WIDTH,
+                    1,
+                    FLOOR,
+                );
+            }
+            Terrain::Pit => {
+                framebuffer.draw_filled_rect(px_x, px_y, CELL_WIDTH, CELL_HEIGHT, FLOOR);
+                framebuffer.draw_filled_rect(
+                    px_x + 2,
+                    px_y + 2,
+                    CELL_WIDTH - 4,
+                    CELL_HEIGHT - 4,
+                    PIT,
+                );
+            }
+            Terrain::Ice => {
+                framebuffer.draw_filled_rect(px_x, px_y, CELL_WIDTH, CELL_HEIGHT, ICE);
+                //a glint
+                framebuffer.draw_filled_rect(px_x + 4, px_y + 4, CELL_WIDTH / 4, 1, WHITE);
+            }
+            Terrain::ChargingPad => {
+                framebuffer.draw_filled_rect(px_x, px_y, CELL_WIDTH, CELL_HEIGHT, FLOOR);
+                framebuffer.draw_rect(
+                    px_x + 3,
+                    px_y + 3,
+                    CELL_WIDTH - 6,
+                    CELL_HEIGHT - 6,
+                    CHARGING_PAD,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use common::*;
+
+pub type Behaviour = fn(&mut GameState, usize);
+
+//Rows are aspects and columns are orb stages, matching the table in Design.md.
+//Aspects past the ones described there just sit still.
+const BEHAVIOURS: [[Behaviour; OrbType::STAGE_COUNT]; 3] = [
+    //Red
+    [seek_nearest, heal_with_ray, go_heal, heal_everyone],
+    //White
+    [stay_centred, form_pattern, enforce_pattern, rearrange_everyone],
+    //Black
+    [flee_danger, seek_healing, follow_white_if_in_power, create_black_orbs],
+];
+
+pub fn behaviour_for(aspect: Aspect, orb_type: OrbType) -> Behaviour {
+    match (BEHAVIOURS.get(aspect.0 as usize), orb_type.stage_index()) {
+        (Some(row), Some(stage)) => row[stage],
+        _ => idle,
+    }
+}
+
+pub fn run_behaviours(state: &mut GameState) {
+    //Decide who acts up front so orbs created this turn wait until the next one.
+    let actors: Vec<usize> = (0..GameState::ENTITY_COUNT)
+        .filter(|&id| state.is_orb(id))
+        .collect();
+
+    for id in actors {
+        //an earlier behaviour may have removed it, and thrown orbs are still in the air
+        if !state.is_orb(id) || state.entities[id].contains(Component::Thrown) {
+            continue;
+        }
+
+        behaviour_for(state.aspects[id], state.orb_types[id])(state, id);
+    }
+}
+
+fn idle(_state: &mut GameState, _id: usize) {}
+
+fn seek_nearest(state: &mut GameState, id: usize) {
+    if let Some(other) = state.nearest_other(id) {
+        let target = state.board_twelfths(other);
+        state.step_towards(id, target);
+    }
+}
+
+fn heal_with_ray(state: &mut GameState, id: usize) {
+    if let Some(target) = state.most_injured_other(id) {
+        let ray = state.cast_ray(id, target);
+
+        let to = match ray.hit {
+            Some(hit) => {
+                state.heal(hit);
+                state.centre_px(hit)
+            }
+            None => {
+                let (x, y) = ray.end;
+                (
+                    (cell_x_to_px_x(x as usize) + CELL_WIDTH / 2) as isize,
+                    (cell_y_to_px_y(y as usize) + CELL_HEIGHT / 2) as isize,
+                )
+            }
+        };
+
+        let colour = state.aspects[id].colour();
+        state.add_beam(id, to, colour);
+    }
+}
+
+//Heals the most injured other thing once it is next to it, and heads towards it until then.
+fn go_heal(state: &mut GameState, id: usize) {
+    if let Some(target) = state.most_injured_other(id) {
+        let (x, y) = state.positions[id];
+        let (target_x, target_y) = state.positions[target];
+        let cells_away = (x as isize - target_x as isize).abs()
+            + (y as isize - target_y as isize).abs();
+
+        if cells_away <= 1 {
+            state.heal(target);
+
+            let to = state.centre_px(target);
+            let colour = state.aspects[id].colour();
+            state.add_beam(id, to, colour);
+        } else {
+            let target = state.board_twelfths(target);
+            state.step_towards(id, target);
+        }
+    }
+}
+
+fn heal_everyone(state: &mut GameState, id: usize) {
+    for other in 0..GameState::ENTITY_COUNT {
+        if other != id && state.is_thing(other) {
+            state.heal(other);
+        }
+    }
+}
+
+//Heads for the nearest red orb that heals things.
+fn seek_healing(state: &mut GameState, id: usize) {
+    let (x, y) = state.board_twelfths(id);
+
+    let healer = orbs_where(state, |other| {
+        other != id && state.aspects[other] == Aspect::Red
+            && (state.orb_types[other] == OrbType::LiveOrb
+                || state.orb_types[other] == OrbType::Blob)
+    }).into_iter()
+        .min_by_key(|&other| {
+            let (other_x, other_y) = state.board_twelfths(other);
+            ((other_x - x).abs() + (other_y - y).abs(), other)
+        });
+
+    if let Some(healer) = healer {
+        let target = state.board_twelfths(healer);
+        state.step_towards(id, target);
+    }
+}
+
+fn stay_centred(state: &mut GameState, id: usize) {
+    let intra_pos = state.intra_cell_positions[id].to_nine();
+
+    let (x, y) = match intra_pos {
+        Nine(pos) => pos.xy(),
+        Four(_) => return,
+    };
+
+    let target = if x != 1 {
+        Nine(_3by3::from_xy(1, y))
+    } else if y != 1 {
+        Nine(_3by3::from_xy(x, 1))
+    } else {
+        return;
+    };
+
+    state.move_within_cell(id, target);
+}
+
+//Orbs that are settled on the board, so not ones that are still in the air.
+fn orbs_where<F>(state: &GameState, predicate: F) -> Vec<usize>
+where
+    F: Fn(usize) -> bool,
+{
+    (0..GameState::ENTITY_COUNT)
+        .filter(|&id| {
+            state.is_orb(id) && !state.entities[id].contains(Component::Thrown) && predicate(id)
+        })
+        .collect()
+}
+
+//The pattern for these orbs to make, avoiding anywhere other things are in the way.
+fn the_pattern(state: &GameState, participants: &[usize]) -> Pattern {
+    Pattern::symmetric(state.board, participants.len(), |pos, slot| {
+        let terrain = state.terrain_at(pos);
+
+        state.positions[state.avatarId] != pos && !terrain.blocks_movement()
+            && !terrain.swallows_orbs() && (0..GameState::ENTITY_COUNT).all(|other| {
+            !state.is_orb(other) || participants.contains(&other)
+                || state.positions[other] != pos
+                || !state.intra_cell_positions[other].overlaps(&Nine(slot))
+        })
+    })
+}
+
+//The slot each participant should head to, in the same order.
+fn plan(state: &GameState, participants: &[usize]) -> Vec<Option<(Position, _3by3)>> {
+    let pattern = the_pattern(state, participants);
+    let points: Vec<(isize, isize)> = participants
+        .iter()
+        .map(|&id| state.board_twelfths(id))
+        .collect();
+
+    pattern
+        .assign(&points)
+        .into_iter()
+        .map(|assigned| assigned.map(|i| pattern.slots[i]))
+        .collect()
+}
+
+fn is_in_slot(state: &GameState, id: usize, (pos, slot): (Position, _3by3)) -> bool {
+    state.positions[id] == pos && state.board_twelfths(id) == board_twelfths(pos, Nine(slot))
+}
+
+fn form_pattern(state: &mut GameState, id: usize) {
+    let participants = orbs_where(state, |other| {
+        state.aspects[other] == Aspect::White && state.orb_types[other] == OrbType::LiveOrb
+    });
+    let targets = plan(state, &participants);
+
+    let target = participants
+        .iter()
+        .position(|&other| other == id)
+        .and_then(|i| targets[i]);
+
+    if let Some(target) = target {
+        if !is_in_slot(state, id, target) {
+            state.step_towards(id, board_twelfths(target.0, Nine(target.1)));
+        }
+    }
+}
+
+//White is in power when there are more white orbs around than black ones. Then it's in
+//this orb's interest to fall in with the white orbs' pattern, otherwise it looks out for itself.
+fn follow_white_if_in_power(state: &mut GameState, id: usize) {
+    let count = |aspect| orbs_where(state, |other| state.aspects[other] == aspect).len();
+
+    if count(Aspect::White) <= count(Aspect::Black) {
+        flee_danger(state, id);
+        return;
+    }
+
+    let mut participants = orbs_where(state, |other| {
+        state.aspects[other] == Aspect::White && state.orb_types[other] == OrbType::LiveOrb
+    });
+    participants.push(id);
+    let targets = plan(state, &participants);
+
+    if let Some(target) = targets[participants.len() - 1] {
+        if !is_in_slot(state, id, target) {
+            state.step_towards(id, board_twelfths(target.0, Nine(target.1)));
+        }
+    }
+}
+
+//Nudges the first orb that is out of place one step towards where it should be.
+fn enforce_pattern(state: &mut GameState, _id: usize) {
+    let participants = orbs_where(state, |other| state.orb_types[other].can_be_carried());
+    let targets = plan(state, &participants);
+
+    for (&other, &target) in participants.iter().zip(targets.iter()) {
+        if let Some(target) = target {
+            if !is_in_slot(state, other, target)
+                && state.step_towards(other, board_twelfths(target.0, Nine(target.1)))
+            {
+                return;
+            }
+        }
+    }
+}
+
+//Everybody moves straight into place at once.
+fn rearrange_everyone(state: &mut GameState, id: usize) {
+    let participants = orbs_where(state, |other| other != id);
+    let targets = plan(state, &participants);
+
+    for (&other, &target) in participants.iter().zip(targets.iter()) {
+        if let Some((pos, slot)) = target {
+            let from = (state.positions[other], state.intra_cell_positions[other]);
+
+            state.positions[other] = pos;
+            state.intra_cell_positions[other] = Nine(slot);
+            state.appearances[other].offset = slide_offset(from, (pos, Nine(slot)));
+        }
+    }
+}
+
+//Heads for whichever of its cell and the neighbouring ones is least dangerous.
+fn flee_danger(state: &mut GameState, id: usize) {
+    let danger_map = state.danger_map();
+    let pos = state.positions[id];
+
+    let safest = Direction::ALL
+        .iter()
+        .filter_map(|direction| direction.step(state.board, pos))
+        .fold(pos, |safest, cell| {
+            if danger_map.get(cell) < danger_map.get(safest) {
+                cell
+            } else {
+                safest
+            }
+        });
+
+    if safest != pos {
+        let (x, y) = safest;
+        state.step_towards(id, (x as isize * 12 + 6, y as isize * 12 + 6));
+    }
+}
+
+fn create_black_orbs(state: &mut GameState, id: usize) {
+    let pos = state.positions[id];
+
+    if let Some(slot) = state.free_slot_in(pos) {
+        state.add_orb(OrbType::DeadOrb, pos, slot, Aspect::Black);
+    }
+}
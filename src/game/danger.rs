@@ -0,0 +1,104 @@
+use common::*;
+
+const AVATAR_DANGER: u8 = 12;
+const AIMED_AT_DANGER: u8 = 8;
+const THROW_LANE_DANGER: u8 = 3;
+const SEEKER_DANGER: u8 = 4;
+const PIT_DANGER: u8 = 16;
+
+//How dangerous each cell of the board is for an orb to be in, from zero upwards.
+pub struct DangerMap {
+    board: Board,
+    cells: Vec<u8>,
+}
+
+impl DangerMap {
+    pub fn get(&self, (x, y): Position) -> u8 {
+        self.board
+            .index(x, y)
+            .map(|i| self.cells[i])
+            .unwrap_or(u8::MAX)
+    }
+
+    fn add(&mut self, (x, y): Position, danger: u8) {
+        if let Some(i) = self.board.index(x, y) {
+            self.cells[i] = self.cells[i].saturating_add(danger);
+        }
+    }
+
+    //`danger` at `pos`, falling off by `falloff` per cell of distance
+    fn add_spread(&mut self, pos: Position, danger: u8, falloff: u8) {
+        for cell in self.board.cells() {
+            let distance = manhattan(pos, cell) as u8;
+            self.add(cell, danger.saturating_sub(distance.saturating_mul(falloff)));
+        }
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer) {
+        for i in 0..self.board.length() {
+            if let Some((x, y)) = self.board.xy(i) {
+                let alpha = (self.cells[i] as u32 * 8).min(0xC0);
+
+                framebuffer.blend_filled_rect(
+                    cell_x_to_px_x(x as usize),
+                    cell_y_to_px_y(y as usize),
+                    CELL_WIDTH,
+                    CELL_HEIGHT,
+                    (RED & 0x00_FF_FF_FF) | alpha << 24,
+                );
+            }
+        }
+    }
+}
+
+fn manhattan(a: Position, b: Position) -> usize {
+    (a.0 as isize - b.0 as isize).unsigned_abs() + (a.1 as isize - b.1 as isize).unsigned_abs()
+}
+
+impl GameState {
+    //Threats are the avatar, the lanes it can throw down, anywhere it is aiming, the red
+    //orbs that chase after anything nearby, and pits.
+    pub fn danger_map(&self) -> DangerMap {
+        let mut map = DangerMap {
+            board: self.board,
+            cells: vec![0; self.board.length()],
+        };
+
+        let avatar_pos = self.positions[self.avatarId];
+        map.add_spread(avatar_pos, AVATAR_DANGER, 3);
+
+        for i in 0..self.board.length() {
+            if let Some(cell) = self.board.xy(i) {
+                if self.terrain[i].swallows_orbs() {
+                    map.add(cell, PIT_DANGER);
+                }
+
+                if cell != avatar_pos && (cell.0 == avatar_pos.0 || cell.1 == avatar_pos.1) {
+                    map.add(cell, THROW_LANE_DANGER);
+                }
+            }
+        }
+
+        if self.mode == Mode::MoveSelectrix {
+            let target = self.positions[self.selectrixId];
+            for cell in cells_between(avatar_pos, target).into_iter().skip(1) {
+                map.add(cell, THROW_LANE_DANGER);
+            }
+            map.add(target, AIMED_AT_DANGER);
+        }
+
+        for id in 0..GameState::ENTITY_COUNT {
+            if !self.is_orb(id) {
+                continue;
+            }
+
+            if self.entities[id].contains(Component::Thrown) {
+                map.add(self.positions[id], AIMED_AT_DANGER);
+            } else if self.aspects[id] == Aspect::Red && self.orb_types[id] == OrbType::DeadOrb {
+                map.add_spread(self.positions[id], SEEKER_DANGER, 2);
+            }
+        }
+
+        map
+    }
+}
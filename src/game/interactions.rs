@@ -0,0 +1,197 @@
+use common::*;
+
+//How two orbs come to meet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Meeting {
+    //they end up overlapping in the same cell during a world turn, in either order
+    Fuse,
+    //the first was thrown and connected with the second
+    Throw,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    First,
+    Second,
+}
+
+//An orb stage, and the aspect it has to be, if it matters.
+pub type OrbMatch = (OrbType, Option<Aspect>);
+
+pub const ANY_ASPECT: Option<Aspect> = None;
+
+//When the first orb meets the second one, both are replaced by an orb of the `produces`
+//stage, made up of both of them, with the aspect of one of them.
+#[derive(Clone, Copy)]
+pub struct Interaction {
+    pub meeting: Meeting,
+    pub first: OrbMatch,
+    pub second: OrbMatch,
+    pub produces: OrbType,
+    pub aspect_of: Side,
+}
+
+//Everything that isn't in here doesn't combine. Thrown orbs that don't combine with anything
+//damage what they hit instead. Check changes with `validate`, which the tests do for this one.
+pub const INTERACTIONS: [Interaction; 3] = [
+    Interaction {
+        meeting: Meeting::Fuse,
+        first: (OrbType::LiveOrb, ANY_ASPECT),
+        second: (OrbType::LiveOrb, ANY_ASPECT),
+        produces: OrbType::Blob,
+        aspect_of: Side::First,
+    },
+    Interaction {
+        meeting: Meeting::Throw,
+        first: (OrbType::LiveOrb, ANY_ASPECT),
+        second: (OrbType::DeadOrb, ANY_ASPECT),
+        produces: OrbType::CreatedOrb,
+        aspect_of: Side::Second,
+    },
+    //The rules above can't put more than two aspects in one orb, so without this one the
+    //win in `check_for_victory` would be out of reach with three or more aspects.
+    Interaction {
+        meeting: Meeting::Throw,
+        first: (OrbType::LiveOrb, ANY_ASPECT),
+        second: (OrbType::Blob, ANY_ASPECT),
+        produces: OrbType::CreatedOrb,
+        aspect_of: Side::Second,
+    },
+];
+
+//The stages that show up without anything combining: orbs start out dead and recharge.
+const NATURAL_STAGES: [OrbType; 2] = [OrbType::DeadOrb, OrbType::LiveOrb];
+
+pub struct Outcome {
+    pub produces: OrbType,
+    pub aspect: Aspect,
+}
+
+fn matches(orb_match: OrbMatch, orb_type: OrbType, aspect: Aspect) -> bool {
+    orb_match.0 == orb_type && orb_match.1.is_none_or(|wanted| wanted == aspect)
+}
+
+//What, if anything, the orbs make when they meet.
+pub fn outcome(
+    meeting: Meeting,
+    first: (OrbType, Aspect),
+    second: (OrbType, Aspect),
+) -> Option<Outcome> {
+    INTERACTIONS.iter().filter(|rule| rule.meeting == meeting).filter_map(|rule| {
+        let matches_in_order = |a: (OrbType, Aspect), b: (OrbType, Aspect)| {
+            matches(rule.first, a.0, a.1) && matches(rule.second, b.0, b.1)
+        };
+
+        let (first, second) = if matches_in_order(first, second) {
+            (first, second)
+        } else if meeting == Meeting::Fuse && matches_in_order(second, first) {
+            (second, first)
+        } else {
+            return None;
+        };
+
+        Some(Outcome {
+            produces: rule.produces,
+            aspect: match rule.aspect_of {
+                Side::First => first.1,
+                Side::Second => second.1,
+            },
+        })
+    }).next()
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Problem {
+    //both of these rules, by index, apply to some of the same orbs
+    Ambiguous(usize, usize),
+    //this rule needs an orb that can never exist
+    Unreachable(usize),
+}
+
+fn could_overlap(a: OrbMatch, b: OrbMatch) -> bool {
+    a.0 == b.0 && match (a.1, b.1) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+fn is_possible(orb_match: OrbMatch) -> bool {
+    orb_match.0 != OrbType::NoOrb && orb_match.1.is_none_or(|aspect| aspect.0 < MAX_ASPECTS)
+}
+
+pub fn validate(interactions: &[Interaction]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (i, a) in interactions.iter().enumerate() {
+        for (j, b) in interactions.iter().enumerate().skip(i + 1) {
+            if a.meeting != b.meeting {
+                continue;
+            }
+
+            let same_order = could_overlap(a.first, b.first) && could_overlap(a.second, b.second);
+            let swapped = a.meeting == Meeting::Fuse && could_overlap(a.first, b.second)
+                && could_overlap(a.second, b.first);
+
+            if same_order || swapped {
+                problems.push(Problem::Ambiguous(i, j));
+            }
+        }
+    }
+
+    let mut reachable: Vec<OrbType> = NATURAL_STAGES.to_vec();
+    loop {
+        let newly_reachable: Vec<OrbType> = interactions
+            .iter()
+            .filter(|rule| {
+                reachable.contains(&rule.first.0) && reachable.contains(&rule.second.0)
+                    && !reachable.contains(&rule.produces)
+            })
+            .map(|rule| rule.produces)
+            .collect();
+
+        if newly_reachable.is_empty() {
+            break;
+        }
+        reachable.extend(newly_reachable);
+    }
+
+    for (i, rule) in interactions.iter().enumerate() {
+        let is_reachable = [rule.first, rule.second]
+            .iter()
+            .all(|&orb_match| is_possible(orb_match) && reachable.contains(&orb_match.0));
+
+        if !is_reachable {
+            problems.push(Problem::Unreachable(i));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_interactions_are_valid() {
+        assert_eq!(validate(&INTERACTIONS), vec![]);
+    }
+
+    #[test]
+    fn validation_catches_ambiguous_and_unreachable_rules() {
+        let red_fusion = Interaction {
+            first: (OrbType::LiveOrb, Some(Aspect::Red)),
+            ..INTERACTIONS[0]
+        };
+        let created_fusion = Interaction {
+            first: (OrbType::CreatedOrb, ANY_ASPECT),
+            second: (OrbType::CreatedOrb, ANY_ASPECT),
+            ..INTERACTIONS[0]
+        };
+
+        assert_eq!(
+            validate(&[INTERACTIONS[0], red_fusion, created_fusion]),
+            vec![Problem::Ambiguous(0, 1), Problem::Unreachable(2)]
+        );
+    }
+}
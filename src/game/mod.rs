@@ -0,0 +1,1403 @@
+mod behaviour;
+mod danger;
+mod interactions;
+mod screens;
+#[cfg(test)]
+mod solver;
+
+use common::*;
+
+use self::interactions::Meeting;
+
+const THROW_DAMAGE: u8 = 1;
+
+//how much dead orbs on charging pads recharge per turn, instead of 1
+const CHARGING_PAD_RATE: u8 = 2;
+
+const BUMP_DISTANCE: isize = (CELL_WIDTH / 2) as isize;
+
+//What happens when the avatar tries to move into a cell.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Occupancy {
+    Free,
+    Blocked,
+    Pickup,
+    Pushable,
+    Splits,
+    Activates,
+}
+
+struct Ray {
+    //the last cell the ray got to
+    end: Position,
+    hit: Option<usize>,
+}
+
+struct ThrowPreview {
+    //in pixels
+    from: (isize, isize),
+    to: (isize, isize),
+    //the orbs it would hit
+    targets: Vec<usize>,
+    //whether it would hit anything, as (hits, out of), if it lands with anything to hit
+    chance: Option<(u8, u8)>,
+}
+
+impl GameState {
+    fn isSelectrix(&self, id: usize) -> bool {
+        self.entities[id].contains(Component::Player | Component::IntraCellPosition)
+            && self.player_types[id] == PlayerType::Selectrix
+    }
+
+    fn isAvatar(&self, id: usize) -> bool {
+        self.entities[id].contains(Component::Player) && self.player_types[id] == PlayerType::Avatar
+    }
+
+    //Where a throw at the selectrix would land. It flies in a straight line, like a ray, so it
+    //comes down in the first cell in the way with anything in it, or short of the first wall.
+    fn throw_landing(&self) -> (Position, IntraCellPosition) {
+        self.throw_landing_at(
+            self.positions[self.selectrixId],
+            self.intra_cell_positions[self.selectrixId],
+        )
+    }
+
+    fn throw_landing_at(
+        &self,
+        target: Position,
+        target_intra: IntraCellPosition,
+    ) -> (Position, IntraCellPosition) {
+        let from = self.positions[self.avatarId];
+
+        let mut landing = from;
+        for cell in cells_between(from, target) {
+            if self.terrain_at(cell).blocks_movement() {
+                break;
+            }
+            landing = cell;
+
+            let is_taken = (0..GameState::ENTITY_COUNT).any(|id| {
+                id != self.avatarId && self.is_thing(id) && self.positions[id] == cell
+            });
+            if cell != from && is_taken {
+                break;
+            }
+        }
+
+        let landing = match Direction::towards(from, target) {
+            Some(direction) => self.slide_on_ice(landing, target_intra, direction),
+            None => landing,
+        };
+
+        (landing, target_intra)
+    }
+
+    fn throw_selected_orb(&mut self) -> bool {
+        let item = *self.inventory.selected();
+        if item.is_empty() {
+            return false;
+        }
+
+        let (target, target_intra) = self.throw_landing();
+
+        match self.add_orb(item.orb_type, target, target_intra, item.aspect) {
+            Some(id) => {
+                self.inventory.remove_selected();
+                self.inventory.select_next();
+
+                self.set_charge(id, item.charge);
+                self.healths[id] = item.health;
+                if item.orb_type == OrbType::Blob || item.orb_type == OrbType::CreatedOrb {
+                    self.entities[id].insert(Component::Constituents);
+                    self.constituents[id] = item.constituents;
+                }
+
+                //what it hits is worked out in the world turn, after the other orbs move
+                self.entities[id].insert(Component::Thrown);
+
+                //slide the orb from the avatar to where it landed
+                let (avatar_x, avatar_y) = self.centre_px(self.avatarId);
+                let (x, y) = intra_px(target, target_intra);
+                self.appearances[id].offset = (avatar_x - x, avatar_y - y);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    //What throwing the selected orb now would do. The orbs get to move before it lands, so it
+    //gets tried out on a copy of the state, up to the point where it would hit something.
+    fn throw_preview(&self) -> Option<ThrowPreview> {
+        let mut after = self.without_history();
+        if !after.throw_selected_orb() {
+            return None;
+        }
+
+        let thrown = (0..GameState::ENTITY_COUNT)
+            .find(|&id| after.is_orb(id) && after.entities[id].contains(Component::Thrown))?;
+        let (pos, intra_pos) = (after.positions[thrown], after.intra_cell_positions[thrown]);
+
+        move_before_throws_land(&mut after);
+
+        let mut targets = Vec::new();
+        let mut chance = None;
+        if after.is_orb(thrown) {
+            let hits = after.throw_hits(thrown);
+            targets = match after.throw_combination(thrown, &hits) {
+                Some((hit, _)) => vec![hit],
+                None => hits,
+            };
+
+            let landed = after.positions[thrown];
+            let can_hit = (0..GameState::ENTITY_COUNT)
+                .any(|id| id != thrown && after.is_orb(id) && after.positions[id] == landed);
+            if can_hit {
+                //nothing moves at random, so it either hits for sure or it doesn't
+                chance = Some((!targets.is_empty() as u8, 1));
+            }
+        }
+        //anything that would only show up during the turn can't be pointed out yet
+        targets.retain(|&id| self.is_orb(id));
+
+        Some(ThrowPreview {
+            from: self.centre_px(self.avatarId),
+            to: intra_px(pos, intra_pos),
+            targets,
+            chance,
+        })
+    }
+
+    //Where something arriving at `pos` while moving in `direction` ends up.
+    fn slide_on_ice(
+        &self,
+        mut pos: Position,
+        intra_pos: IntraCellPosition,
+        direction: Direction,
+    ) -> Position {
+        while self.terrain_at(pos) == Terrain::Ice {
+            match direction.step(self.board, pos) {
+                Some(next) if next != self.positions[self.avatarId]
+                    && self.is_slot_free(next, intra_pos) =>
+                {
+                    pos = next
+                }
+                _ => break,
+            }
+        }
+
+        pos
+    }
+
+    //The orbs the thrown orb connects with, where it is now.
+    fn throw_hits(&self, thrown: usize) -> Vec<usize> {
+        let pos = self.positions[thrown];
+        let thrown_to = match self.intra_cell_positions[thrown] {
+            Four(slot) => slot,
+            Nine(_) => return Vec::new(),
+        };
+
+        (0..GameState::ENTITY_COUNT)
+            .filter(|&id| {
+                id != thrown && self.is_orb(id) && self.positions[id] == pos
+                    && throw_connects(thrown_to, self.intra_cell_positions[id])
+            })
+            .collect()
+    }
+
+    //The first of the `hits` the thrown orb combines with, if any.
+    fn throw_combination(
+        &self,
+        thrown: usize,
+        hits: &[usize],
+    ) -> Option<(usize, interactions::Outcome)> {
+        let thrown_orb = (self.orb_types[thrown], self.aspects[thrown]);
+
+        hits.iter().filter_map(|&id| {
+            let hit_orb = (self.orb_types[id], self.aspects[id]);
+
+            interactions::outcome(Meeting::Throw, thrown_orb, hit_orb).map(|outcome| (id, outcome))
+        }).next()
+    }
+
+    fn resolve_throw_impact(&mut self, thrown: usize) {
+        self.entities[thrown].remove(Component::Thrown);
+
+        let pos = self.positions[thrown];
+        let hits = self.throw_hits(thrown);
+
+        match self.throw_combination(thrown, &hits) {
+            Some((hit, outcome)) => {
+                let mut constituents = self.constituents_of(thrown);
+                constituents.extend(&self.constituents_of(hit));
+
+                let hit_intra_pos = self.intra_cell_positions[hit];
+
+                self.remove_entity(thrown);
+                self.remove_entity(hit);
+
+                if let Some(created) =
+                    self.add_orb(outcome.produces, pos, hit_intra_pos, outcome.aspect)
+                {
+                    self.entities[created].insert(Component::Constituents);
+                    self.constituents[created] = constituents;
+                }
+            }
+            None => for id in hits {
+                self.damage(id, THROW_DAMAGE);
+            },
+        }
+    }
+
+    fn damage(&mut self, id: usize, amount: u8) {
+        if !self.entities[id].contains(Component::Health) {
+            return;
+        }
+
+        self.healths[id] = self.healths[id].saturating_sub(amount);
+
+        if self.healths[id] == 0 {
+            self.remove_entity(id);
+        }
+    }
+
+    fn heal(&mut self, id: usize) {
+        if self.entities[id].contains(Component::Health) && self.healths[id] < MAX_HEALTH {
+            self.healths[id] += 1;
+        }
+    }
+
+    fn is_injured(&self, id: usize) -> bool {
+        self.entities[id].contains(Component::Health) && self.healths[id] < MAX_HEALTH
+    }
+
+    //Ties go to the lowest id.
+    fn most_injured_other(&self, id: usize) -> Option<usize> {
+        (0..GameState::ENTITY_COUNT)
+            .filter(|&other| other != id && self.is_thing(other) && self.is_injured(other))
+            .min_by_key(|&other| (self.healths[other], other))
+    }
+
+    //Casts a ray in a straight line, cell by cell, from `caster` towards `target`, stopping at
+    //the first thing in the way, or before the first wall. Whatever is in the cell with `target`
+    //blocks it too, but if `target` itself is in a cell the ray reaches, that's what it hits.
+    //Anything else in the caster's own cell is behind it, so doesn't get in the way.
+    fn cast_ray(&self, caster: usize, target: usize) -> Ray {
+        let from = self.positions[caster];
+        let mut end = from;
+
+        for cell in cells_between(from, self.positions[target]) {
+            if self.terrain_at(cell).blocks_movement() {
+                break;
+            }
+            end = cell;
+
+            if self.positions[target] == cell {
+                return Ray {
+                    end,
+                    hit: Some(target),
+                };
+            }
+
+            if cell == from {
+                continue;
+            }
+
+            let hit = (0..GameState::ENTITY_COUNT).find(|&id| {
+                id != caster && self.is_thing(id) && self.positions[id] == cell
+            });
+
+            if hit.is_some() {
+                return Ray { end, hit };
+            }
+        }
+
+        Ray { end, hit: None }
+    }
+
+    fn centre_px(&self, id: usize) -> (isize, isize) {
+        if self.entities[id].contains(Component::IntraCellPosition) {
+            intra_px(self.positions[id], self.intra_cell_positions[id])
+        } else {
+            let (x, y) = self.positions[id];
+
+            (
+                (cell_x_to_px_x(x as usize) + CELL_WIDTH / 2) as isize,
+                (cell_y_to_px_y(y as usize) + CELL_HEIGHT / 2) as isize,
+            )
+        }
+    }
+
+    fn add_beam(&mut self, from: usize, to: (isize, isize), colour: u32) {
+        let from = self.centre_px(from);
+
+        self.beams.push(Beam {
+            from,
+            to,
+            colour,
+            frames_left: BEAM_FRAMES,
+        });
+    }
+
+    fn most_charged_at(&self, pos: Position) -> Option<u8> {
+        (0..GameState::ENTITY_COUNT)
+            .filter(|&id| {
+                self.is_orb(id) && self.entities[id].contains(Component::Charge)
+                    && self.positions[id] == pos
+            })
+            .map(|id| self.charges[id])
+            .max()
+    }
+
+    fn is_cell_occupied(&self, pos: Position) -> bool {
+        self.positions[self.avatarId] == pos
+            || (0..GameState::ENTITY_COUNT).any(|id| self.is_orb(id) && self.positions[id] == pos)
+    }
+
+    fn is_slot_free(&self, pos: Position, intra_pos: IntraCellPosition) -> bool {
+        !self.terrain_at(pos).blocks_movement() && !(0..GameState::ENTITY_COUNT).any(|id| {
+            self.is_orb(id) && self.positions[id] == pos
+                && self.intra_cell_positions[id].overlaps(&intra_pos)
+        })
+    }
+
+    //ignoring whatever `id` itself overlaps, and any orbs it would fuse with
+    fn is_slot_free_for(&self, id: usize, pos: Position, intra_pos: IntraCellPosition) -> bool {
+        !self.terrain_at(pos).blocks_movement() && !(0..GameState::ENTITY_COUNT).any(|other| {
+            other != id && self.is_orb(other) && self.positions[other] == pos
+                && self.intra_cell_positions[other].overlaps(&intra_pos)
+                && !self.would_fuse(id, other)
+        })
+    }
+
+    fn would_fuse(&self, id: usize, other: usize) -> bool {
+        interactions::outcome(
+            Meeting::Fuse,
+            (self.orb_types[id], self.aspects[id]),
+            (self.orb_types[other], self.aspects[other]),
+        ).is_some()
+    }
+
+    fn move_within_cell(&mut self, id: usize, intra_pos: IntraCellPosition) -> bool {
+        let pos = self.positions[id];
+
+        self.move_orb(id, pos, intra_pos)
+    }
+
+    //Moves the orb if there's room for it there, sliding it over from where it was.
+    fn move_orb(&mut self, id: usize, pos: Position, intra_pos: IntraCellPosition) -> bool {
+        if self.positions[self.avatarId] == pos || !self.is_slot_free_for(id, pos, intra_pos) {
+            return false;
+        }
+
+        let from = (self.positions[id], self.intra_cell_positions[id]);
+        self.positions[id] = pos;
+        self.intra_cell_positions[id] = intra_pos;
+        self.appearances[id].offset = slide_offset(from, (pos, intra_pos));
+
+        true
+    }
+
+    fn is_thing(&self, id: usize) -> bool {
+        self.is_orb(id)
+            || (self.entities[id].contains(Component::Position)
+                && (self.isAvatar(id) || self.player_types[id] == PlayerType::Ally))
+    }
+
+    //The centre of the entity, in twelfths of a cell, across the whole board.
+    fn board_twelfths(&self, id: usize) -> (isize, isize) {
+        if self.entities[id].contains(Component::IntraCellPosition) {
+            board_twelfths(self.positions[id], self.intra_cell_positions[id])
+        } else {
+            let (x, y) = self.positions[id];
+            (x as isize * 12 + 6, y as isize * 12 + 6)
+        }
+    }
+
+    //Ties go to the lowest id, so replays come out the same.
+    fn nearest_other(&self, id: usize) -> Option<usize> {
+        let (x, y) = self.board_twelfths(id);
+
+        (0..GameState::ENTITY_COUNT)
+            .filter(|&other| other != id && self.is_thing(other))
+            .min_by_key(|&other| {
+                let (other_x, other_y) = self.board_twelfths(other);
+                ((other_x - x).abs() + (other_y - y).abs(), other)
+            })
+    }
+
+    //Takes one 3 by 3 step towards the target, along the axis it is furthest away on first.
+    fn step_towards(&mut self, id: usize, target: (isize, isize)) -> bool {
+        let (x, y) = self.board_twelfths(id);
+        let (dx, dy) = (target.0 - x, target.1 - y);
+
+        let horizontal = if dx < 0 {
+            Some(Direction::Left)
+        } else if dx > 0 {
+            Some(Direction::Right)
+        } else {
+            None
+        };
+        let vertical = if dy < 0 {
+            Some(Direction::Up)
+        } else if dy > 0 {
+            Some(Direction::Down)
+        } else {
+            None
+        };
+
+        let directions = if dx.abs() >= dy.abs() {
+            [horizontal, vertical]
+        } else {
+            [vertical, horizontal]
+        };
+
+        let pos = self.positions[id];
+        let intra_pos = self.intra_cell_positions[id].to_nine();
+
+        directions.iter().filter_map(|&d| d).any(|direction| {
+            match direction.step_intra(self.board, pos, intra_pos) {
+                Some((new_pos, new_intra_pos)) => self.move_orb(id, new_pos, new_intra_pos),
+                None => false,
+            }
+        })
+    }
+
+    fn free_slot_in(&self, pos: Position) -> Option<IntraCellPosition> {
+        if self.positions[self.avatarId] == pos {
+            return None;
+        }
+
+        _3by3::ALL
+            .iter()
+            .map(|&slot| Nine(slot))
+            .find(|&slot| self.is_slot_free(pos, slot))
+    }
+
+    fn split_blobs_at(&mut self, pos: Position) {
+        for id in 0..GameState::ENTITY_COUNT {
+            if !self.is_orb(id) || self.orb_types[id] != OrbType::Blob || self.positions[id] != pos
+            {
+                continue;
+            }
+
+            let constituents = self.constituents_of(id);
+            let from = (pos, self.intra_cell_positions[id]);
+            let mut leftovers = Constituents::default();
+
+            let neighbours: Vec<Position> =
+                Direction::ALL.iter().filter_map(|dir| dir.step(self.board, pos)).collect();
+
+            for (k, &constituent) in constituents.as_slice().iter().enumerate() {
+                //spread the orbs around rather than filling up one neighbour first
+                let target = (0..neighbours.len())
+                    .map(|offset| neighbours[(k + offset) % neighbours.len()])
+                    .chain(Some(pos))
+                    .filter_map(|cell| self.free_slot_in(cell).map(|slot| (cell, slot)))
+                    .next();
+
+                let orb = target.and_then(|to| {
+                    self.add_orb(constituent.orb_type, to.0, to.1, constituent.aspect)
+                        .map(|orb| (orb, to))
+                });
+
+                match orb {
+                    Some((orb, to)) => self.appearances[orb].offset = slide_offset(from, to),
+                    None => {
+                        leftovers.push(constituent);
+                    }
+                }
+            }
+
+            self.keep_leftovers(id, leftovers);
+        }
+    }
+
+    //Whatever couldn't get out of `id` stays behind in it, so no aspects get lost.
+    fn keep_leftovers(&mut self, id: usize, leftovers: Constituents) {
+        match leftovers.as_slice().len() {
+            0 => self.remove_entity(id),
+            1 => {
+                let only = leftovers.as_slice()[0];
+                let (pos, intra_pos) = (self.positions[id], self.intra_cell_positions[id]);
+
+                //this can't fail, since it gets the id that was just freed up
+                self.remove_entity(id);
+                self.add_orb(only.orb_type, pos, intra_pos, only.aspect);
+            }
+            _ => {
+                self.entities[id].insert(Component::Constituents);
+                self.constituents[id] = leftovers;
+            }
+        }
+    }
+
+    fn activate_created_orbs_at(&mut self, pos: Position) {
+        for id in 0..GameState::ENTITY_COUNT {
+            if !self.is_orb(id) || self.orb_types[id] != OrbType::CreatedOrb
+                || self.positions[id] != pos
+            {
+                continue;
+            }
+
+            let constituents = self.constituents_of(id);
+            let from = (pos, self.intra_cell_positions[id]);
+            let mut leftovers = Constituents::default();
+
+            for (k, &constituent) in constituents.as_slice().iter().enumerate() {
+                let direction = Direction::ALL[k % Direction::ALL.len()];
+
+                //fly until the next cell has something in it, or is off the board
+                let mut landing = pos;
+                while let Some(next) = direction.step(self.board, landing) {
+                    if self.is_cell_occupied(next) || self.terrain_at(next).blocks_movement() {
+                        break;
+                    }
+                    landing = next;
+                }
+
+                let target = self.free_slot_in(landing)
+                    .map(|slot| (landing, slot))
+                    .or_else(|| self.free_slot_in(pos).map(|slot| (pos, slot)));
+
+                let orb = target.and_then(|to| {
+                    self.add_orb(constituent.orb_type, to.0, to.1, constituent.aspect)
+                        .map(|orb| (orb, to))
+                });
+
+                match orb {
+                    Some((orb, to)) => self.appearances[orb].offset = slide_offset(from, to),
+                    None => {
+                        leftovers.push(constituent);
+                    }
+                }
+            }
+
+            self.keep_leftovers(id, leftovers);
+        }
+    }
+
+    fn is_settled(&self) -> bool {
+        !(0..GameState::ENTITY_COUNT).any(|id| {
+            !self.isSelectrix(id) && self.entities[id].contains(Component::Appearance)
+                && self.appearances[id].is_offset()
+        })
+    }
+
+    fn occupancy(&self, pos: Position) -> Occupancy {
+        let occupants: Vec<usize> = (0..GameState::ENTITY_COUNT)
+            .filter(|&id| id != self.avatarId && self.is_thing(id) && self.positions[id] == pos)
+            .collect();
+
+        if self.terrain_at(pos).blocks_movement() {
+            return Occupancy::Blocked;
+        }
+
+        if occupants.is_empty() {
+            return Occupancy::Free;
+        }
+
+        let any = |orb_type| occupants.iter().any(|&id| self.orb_types[id] == orb_type);
+
+        if occupants.iter().any(|&id| !self.is_orb(id)) {
+            Occupancy::Blocked
+        } else if any(OrbType::Blob) {
+            Occupancy::Splits
+        } else if any(OrbType::CreatedOrb) {
+            Occupancy::Activates
+        } else if occupants.iter().all(|&id| self.is_pushable(id)) {
+            Occupancy::Pushable
+        } else if occupants.iter().all(|&id| self.orb_types[id].can_be_carried())
+            && occupants.len() <= self.inventory.free_slots()
+        {
+            Occupancy::Pickup
+        } else {
+            Occupancy::Blocked
+        }
+    }
+
+    fn is_pushable(&self, id: usize) -> bool {
+        self.orb_types[id] == OrbType::DeadOrb && self.is_full_of_dead_orbs(self.positions[id])
+    }
+
+    fn is_full_of_dead_orbs(&self, pos: Position) -> bool {
+        self.free_slot_in(pos).is_none() && (0..GameState::ENTITY_COUNT).all(|id| {
+            !self.is_orb(id) || self.positions[id] != pos || self.orb_types[id] == OrbType::DeadOrb
+        })
+    }
+
+    //returns whether that took a turn
+    fn move_avatar(&mut self, direction: Direction) -> bool {
+        let id = self.avatarId;
+        let pos = self.positions[id];
+
+        let target = match direction.step(self.board, pos) {
+            Some(target) => target,
+            None => {
+                self.bump(id, direction);
+                return false;
+            }
+        };
+
+        //orbs can be pushed into pits, but the avatar won't walk into one
+        if self.terrain_at(target).swallows_orbs() {
+            self.bump(id, direction);
+            return false;
+        }
+
+        match self.occupancy(target) {
+            Occupancy::Free => {}
+            Occupancy::Pickup => self.pick_up_orbs_at(target),
+            Occupancy::Pushable => if !self.push_from(target, direction) {
+                self.bump(id, direction);
+                return false;
+            },
+            Occupancy::Splits => {
+                self.bump(id, direction);
+                self.split_blobs_at(target);
+                return true;
+            }
+            Occupancy::Activates => {
+                self.bump(id, direction);
+                self.activate_created_orbs_at(target);
+                return true;
+            }
+            Occupancy::Blocked => {
+                self.bump(id, direction);
+                return false;
+            }
+        }
+
+        let (dx, dy) = direction.delta();
+        self.positions[id] = target;
+        self.appearances[id].offset = (-dx * CELL_WIDTH as isize, -dy * CELL_HEIGHT as isize);
+
+        true
+    }
+
+    //Nudges the entity towards where it tried to go, so it springs back.
+    fn bump(&mut self, id: usize, direction: Direction) {
+        let (dx, dy) = direction.delta();
+
+        self.appearances[id].offset = (dx * BUMP_DISTANCE, dy * BUMP_DISTANCE);
+    }
+
+    fn push_from(&mut self, pos: Position, direction: Direction) -> bool {
+        let next = match direction.step(self.board, pos) {
+            Some(next) => next,
+            None => return false,
+        };
+
+        let can_move = match self.occupancy(next) {
+            Occupancy::Free => true,
+            Occupancy::Pushable => self.chained_pushes && self.push_from(next, direction),
+            _ => false,
+        };
+
+        if !can_move {
+            return false;
+        }
+
+        let pushed: Vec<usize> = (0..GameState::ENTITY_COUNT)
+            .filter(|&id| self.is_thing(id) && self.positions[id] == pos && self.is_pushable(id))
+            .collect();
+
+        for id in pushed {
+            let intra_pos = self.intra_cell_positions[id];
+
+            self.positions[id] = next;
+            self.appearances[id].offset = slide_offset((pos, intra_pos), (next, intra_pos));
+        }
+
+        true
+    }
+
+    fn pick_up_orbs_at(&mut self, pos: Position) {
+        for id in 0..GameState::ENTITY_COUNT {
+            if !self.is_orb(id) || self.positions[id] != pos || !self.orb_types[id].can_be_carried()
+            {
+                continue;
+            }
+
+            let item = InventoryItem {
+                orb_type: self.orb_types[id],
+                aspect: self.aspects[id],
+                charge: self.charges[id],
+                health: self.healths[id],
+                constituents: self.constituents[id],
+            };
+
+            if self.inventory.add(item) {
+                self.remove_entity(id);
+            } else {
+                return;
+            }
+        }
+    }
+}
+
+fn advance_world_turn(state: &mut GameState) {
+    move_before_throws_land(state);
+    resolve_throws(state);
+    fuse_orbs(state);
+    check_for_victory(state);
+    check_for_game_over(state);
+
+    state.record_turn();
+}
+
+//If you combine all the aspects you create an entity identical to yourself and win.
+fn check_for_victory(state: &mut GameState) {
+    let all_aspects = state.all_aspects_mask();
+
+    let ally = (0..GameState::ENTITY_COUNT).find(|&id| {
+        state.is_orb(id) && state.entities[id].contains(Component::Constituents)
+            && state.constituents[id].aspect_mask() & all_aspects == all_aspects
+    });
+
+    if let Some(id) = ally {
+        state.entities[id].remove(Component::Orb | Component::IntraCellPosition);
+        state.appearances[id].colour = state.appearances[state.avatarId].colour;
+        state.appearances[id].shape = Shape::Player;
+        state.player_types[id] = PlayerType::Ally;
+
+        state.entities[state.selectrixId].remove(Component::Appearance);
+        state.mode = Mode::MoveAvatar;
+        state.scene = Scene::LevelComplete;
+    }
+}
+
+//Once every orb of some aspect is gone, that aspect can't be part of an ally any more.
+fn check_for_game_over(state: &mut GameState) {
+    let all_aspects = state.all_aspects_mask();
+
+    if state.scene == Scene::Playing && remaining_aspects(state) & all_aspects != all_aspects {
+        state.scene = Scene::GameOver;
+    }
+}
+
+//The aspects of every orb still on the board or in the inventory, including fused ones.
+fn remaining_aspects(state: &GameState) -> u32 {
+    let on_board = (0..GameState::ENTITY_COUNT)
+        .filter(|&id| state.is_orb(id))
+        .fold(0, |mask, id| mask | state.constituents_of(id).aspect_mask());
+
+    (0..INVENTORY_SIZE)
+        .map(|i| state.inventory.get(i))
+        .filter(|item| !item.is_empty())
+        .fold(on_board, |mask, item| {
+            mask | 1 << item.aspect.0 | item.constituents.aspect_mask()
+        })
+}
+
+//Everything that happens in a world turn before thrown orbs hit anything.
+fn move_before_throws_land(state: &mut GameState) {
+    recharge_dead_orbs(state);
+    behaviour::run_behaviours(state);
+    swallow_orbs_in_pits(state);
+}
+
+fn resolve_throws(state: &mut GameState) {
+    for i in 0..GameState::ENTITY_COUNT {
+        if state.is_orb(i) && state.entities[i].contains(Component::Thrown) {
+            state.resolve_throw_impact(i);
+        }
+    }
+}
+
+fn swallow_orbs_in_pits(state: &mut GameState) {
+    for i in 0..GameState::ENTITY_COUNT {
+        if state.is_orb(i) && state.terrain_at(state.positions[i]).swallows_orbs() {
+            state.remove_entity(i);
+        }
+    }
+}
+
+fn recharge_dead_orbs(state: &mut GameState) {
+    for i in 0..GameState::ENTITY_COUNT {
+        if !state.is_orb(i) || !state.entities[i].contains(Component::Charge)
+            || state.orb_types[i] != OrbType::DeadOrb
+        {
+            continue;
+        }
+
+        let rate = if state.terrain_at(state.positions[i]) == Terrain::ChargingPad {
+            CHARGING_PAD_RATE
+        } else {
+            1
+        };
+
+        let charge = state.charges[i].saturating_add(rate);
+        state.set_charge(i, charge);
+
+        if charge >= FULL_CHARGE {
+            state.orb_types[i] = OrbType::LiveOrb;
+            state.appearances[i].shape = Shape::LiveOrb0;
+        }
+    }
+}
+
+//Orbs that end up overlapping each other may combine, see `interactions::INTERACTIONS`.
+fn fuse_orbs(state: &mut GameState) {
+    for i in 0..GameState::ENTITY_COUNT {
+        if !state.is_orb(i) {
+            continue;
+        }
+
+        for j in (i + 1)..GameState::ENTITY_COUNT {
+            if !state.is_orb(j) || state.positions[i] != state.positions[j]
+                || !state.intra_cell_positions[i].overlaps(&state.intra_cell_positions[j])
+            {
+                continue;
+            }
+
+            let outcome = match interactions::outcome(
+                Meeting::Fuse,
+                (state.orb_types[i], state.aspects[i]),
+                (state.orb_types[j], state.aspects[j]),
+            ) {
+                Some(outcome) => outcome,
+                None => continue,
+            };
+
+            let mut constituents = state.constituents_of(i);
+            constituents.extend(&state.constituents_of(j));
+
+            let pos = state.positions[i];
+            let intra_pos = state.intra_cell_positions[i];
+
+            state.remove_entity(i);
+            state.remove_entity(j);
+
+            if let Some(fused) = state.add_orb(outcome.produces, pos, intra_pos, outcome.aspect) {
+                state.entities[fused].insert(Component::Constituents);
+                state.constituents[fused] = constituents;
+            }
+
+            break;
+        }
+    }
+}
+
+//So whoever is editing the interactions hears about any mistakes in them.
+pub fn interaction_problems() -> Vec<String> {
+    interactions::validate(&interactions::INTERACTIONS)
+        .iter()
+        .map(|problem| format!("{:?} in interactions::INTERACTIONS", problem))
+        .collect()
+}
+
+#[inline]
+pub fn update_and_render(state: &mut GameState, framebuffer: &mut Framebuffer, input: Input) {
+    update(state, input);
+    render(state, framebuffer);
+}
+
+fn update(state: &mut GameState, input: Input) {
+    //Start+Select is taken by the danger map
+    let start = input.pressed_this_frame(Button::Start) && !input.gamepad.contains(Button::Select);
+
+    match state.scene {
+        Scene::Title | Scene::Paused => {
+            if start {
+                state.scene = Scene::Playing;
+            }
+            return;
+        }
+        Scene::Playing if start => {
+            state.scene = Scene::Paused;
+            return;
+        }
+        Scene::LevelComplete if start => {
+            let seed = state.rng.next_u32();
+            *state = generator::generate(seed);
+            return;
+        }
+        Scene::GameOver if start => {
+            //back to the start of the same level, to have another go at it
+            *state = match state.seed {
+                Some(seed) => generator::generate(seed),
+                None => GameState::new(),
+            };
+            state.scene = Scene::Playing;
+            return;
+        }
+        Scene::Playing | Scene::LevelComplete | Scene::GameOver => {}
+    }
+
+    let mut took_turn = false;
+
+    //Select+B undoes a world turn and Select+A redoes one. Either way nothing else should see
+    //those buttons this frame.
+    let input = if state.turn_pending {
+        input
+    } else if input.pressed_this_frame(Button::Select | Button::B) {
+        state.undo();
+        Input {
+            previous_gamepad: input.gamepad,
+            ..input
+        }
+    } else if input.pressed_this_frame(Button::Select | Button::A) {
+        state.redo();
+        Input {
+            previous_gamepad: input.gamepad,
+            ..input
+        }
+    } else {
+        input
+    };
+
+    //once the level is over only undoing and redoing can change anything
+    let input = if state.scene == Scene::Playing {
+        input
+    } else {
+        Input {
+            previous_gamepad: input.gamepad,
+            ..input
+        }
+    };
+
+    for i in 0..GameState::ENTITY_COUNT {
+        if state.mode == Mode::MoveAvatar && state.isAvatar(i) {
+            let appearance = &mut state.appearances[i];
+
+            //the rest of the world gets to finish its turn before the avatar acts again
+            if appearance.is_offset() || state.turn_pending {
+                appearance.reduce_offset(8);
+                continue;
+            }
+
+            let direction = [
+                (Button::Left, Direction::Left),
+                (Button::Right, Direction::Right),
+                (Button::Up, Direction::Up),
+                (Button::Down, Direction::Down),
+            ].iter()
+                .find(|&&(button, _)| input.pressed_this_frame(button))
+                .map(|&(_, direction)| direction);
+
+            if let Some(direction) = direction {
+                took_turn = state.move_avatar(direction);
+            }
+        } else if state.mode == Mode::MoveSelectrix && state.isSelectrix(i) {
+            let appearance = &mut state.appearances[i];
+
+            if appearance.is_offset() {
+                appearance.reduce_offset(8);
+                continue;
+            }
+
+            let (mut x, mut y) = state.positions[i];
+            let mut inter_pos = state.intra_cell_positions[i];
+
+            for &(button, direction) in [
+                (Button::Left, Direction::Left),
+                (Button::Right, Direction::Right),
+                (Button::Up, Direction::Up),
+                (Button::Down, Direction::Down),
+            ].iter()
+            {
+                if !input.pressed_this_frame(button) {
+                    continue;
+                }
+
+                let stepped = direction.step_intra(state.board, (x, y), inter_pos);
+                if let Some((pos, intra_pos)) = stepped {
+                    x = pos.0;
+                    y = pos.1;
+                    inter_pos = intra_pos;
+
+                    let (dx, dy) = direction.delta();
+                    if dx != 0 {
+                        appearance.offset.0 = -dx * (CELL_WIDTH / 2) as isize;
+                    }
+                    if dy != 0 {
+                        appearance.offset.1 = -dy * (CELL_HEIGHT / 2) as isize;
+                    }
+                }
+            }
+
+            state.positions[i] = (x, y);
+            state.intra_cell_positions[i] = inter_pos;
+        } else if !state.isSelectrix(i) {
+            state.appearances[i].reduce_offset(8);
+        }
+    }
+
+    state.mode = match state.mode {
+        Mode::MoveAvatar if input.pressed_this_frame(Button::B) => {
+            state.positions[state.selectrixId] = state.positions[state.avatarId];
+            state.entities[state.selectrixId].insert(Component::Appearance);
+
+            Mode::MoveSelectrix
+        }
+        Mode::MoveSelectrix if input.pressed_this_frame(Button::B) => {
+            state.entities[state.selectrixId].remove(Component::Appearance);
+
+            Mode::MoveAvatar
+        }
+        Mode::MoveSelectrix if input.pressed_this_frame(Button::A) && !state.turn_pending => {
+            if state.throw_selected_orb() {
+                state.entities[state.selectrixId].remove(Component::Appearance);
+                took_turn = true;
+
+                Mode::MoveAvatar
+            } else {
+                state.mode
+            }
+        }
+        _ => state.mode,
+    };
+
+    if took_turn {
+        state.turn_pending = true;
+    }
+
+    //Waiting for everything to stop sliding means a turn always starts from where things look
+    //like they are, and that the number of turns taken doesn't depend on the frame rate.
+    if state.turn_pending && state.is_settled() {
+        state.turn_pending = false;
+        state.turn = state.turn.wrapping_add(1);
+        advance_world_turn(state);
+    }
+
+    if input.pressed_this_frame(Button::Start | Button::Select) {
+        state.show_danger_map = !state.show_danger_map;
+    } else if input.pressed_this_frame(Button::Select) {
+        state.inventory.select_next();
+    }
+
+    for beam in state.beams.iter_mut() {
+        beam.frames_left = beam.frames_left.saturating_sub(1);
+    }
+    state.beams.retain(|beam| beam.frames_left > 0);
+}
+
+fn render(state: &GameState, framebuffer: &mut Framebuffer) {
+    if state.scene == Scene::Title {
+        screens::render_title(framebuffer);
+        return;
+    }
+
+    framebuffer.clear();
+
+    for (i, pos) in state.board.cells().into_iter().enumerate() {
+        state.terrain[i].render(framebuffer, pos);
+    }
+
+    for i in 0..GameState::ENTITY_COUNT {
+        let entity = state.entities[i];
+        if state.isSelectrix(i) {
+            if state.mode == Mode::MoveSelectrix {
+                let pos = state.positions[i];
+                let inter_pos = state.intra_cell_positions[i];
+
+                let appearance = &state.appearances[i];
+                appearance.render_intra_positioned(framebuffer, pos, inter_pos);
+            }
+        } else if entity
+            .contains(Component::Position | Component::Appearance | Component::IntraCellPosition)
+        {
+            let pos = state.positions[i];
+            let inter_pos = state.intra_cell_positions[i];
+
+            let appearance = &state.appearances[i];
+            appearance.render_intra_positioned(framebuffer, pos, inter_pos);
+            let (x_off, y_off) = appearance.offset;
+
+            if state.is_injured(i) {
+                let (x, y) = intra_px(pos, inter_pos);
+
+                framebuffer.draw_filled_rect(
+                    (x + x_off) as usize - ORB_RADIUS,
+                    (y + y_off) as usize + ORB_RADIUS + 2,
+                    ORB_RADIUS * 2 * state.healths[i] as usize / MAX_HEALTH as usize,
+                    1,
+                    GREEN,
+                );
+            }
+        } else if entity.contains(Component::Position | Component::Appearance) {
+            let pos = state.positions[i];
+
+            let appearance = &state.appearances[i];
+            appearance.render_positioned(framebuffer, pos);
+        }
+    }
+
+    for beam in state.beams.iter() {
+        framebuffer.draw_line(beam.from, beam.to, beam.colour);
+    }
+
+    let throw_preview = if state.mode == Mode::MoveSelectrix {
+        state.throw_preview()
+    } else {
+        None
+    };
+
+    if let Some(ref preview) = throw_preview {
+        framebuffer.draw_dotted_line(preview.from, preview.to, WHITE);
+
+        for &id in preview.targets.iter() {
+            let (x, y) = state.centre_px(id);
+            let (x_off, y_off) = state.appearances[id].offset;
+            let half_size = ORB_RADIUS + 2;
+
+            framebuffer.draw_rect(
+                (x + x_off - half_size as isize).max(0) as usize,
+                (y + y_off - half_size as isize).max(0) as usize,
+                half_size * 2 + 1,
+                half_size * 2 + 1,
+                YELLOW,
+            );
+        }
+    }
+
+    if state.show_danger_map {
+        state.danger_map().render(framebuffer);
+    }
+
+    let hud_left_edge = state.board.hud_left_edge();
+    let inventory_left_edge = state.board.inventory_left_edge();
+    let inventory_width = state.board.inventory_width();
+    let inventory_height = inventory_width;
+
+    framebuffer.draw_filled_rect(hud_left_edge, 0, state.board.hud_width(), SCREEN_HEIGHT, GREY);
+
+    for i in 0..INVENTORY_SIZE {
+        let item = state.inventory.get(i);
+
+        let x = inventory_left_edge;
+        let y = (inventory_height + 4) * (i + 1);
+
+        framebuffer.draw_filled_rect(x, y, inventory_width, inventory_height, PURPLE);
+
+        if let Some(appearance) = item.appearance() {
+            appearance.render_at_px(framebuffer, x + inventory_width / 2, y + inventory_height / 2);
+        }
+
+        if i as u8 == state.inventory.index {
+            framebuffer.draw_rect(x, y, inventory_width, inventory_height, YELLOW);
+        }
+    }
+
+    if state.mode == Mode::MoveSelectrix {
+        if let Some(charge) = state.most_charged_at(state.positions[state.selectrixId]) {
+            let x = inventory_left_edge;
+            let y = (inventory_height + 4) * (INVENTORY_SIZE + 1);
+
+            framebuffer.draw_filled_rect(x, y, inventory_width, CHARGE_BAR_HEIGHT, PURPLE);
+            framebuffer.draw_filled_rect(
+                x,
+                y,
+                inventory_width * charge as usize / FULL_CHARGE as usize,
+                CHARGE_BAR_HEIGHT,
+                YELLOW,
+            );
+        }
+    }
+
+    if let Some((hits, out_of)) = throw_preview.and_then(|preview| preview.chance) {
+        let x = inventory_left_edge;
+        let y = (inventory_height + 4) * (INVENTORY_SIZE + 1) + CHARGE_BAR_HEIGHT + 4;
+        let hits_width = hits.to_string().len() * (DIGIT_WIDTH + 1);
+
+        framebuffer.draw_number(x, y, hits as u32, WHITE);
+        //a slash between the two
+        framebuffer.draw_line(
+            ((x + hits_width) as isize, (y + DIGIT_HEIGHT - 1) as isize),
+            ((x + hits_width + DIGIT_WIDTH - 1) as isize, y as isize),
+            WHITE,
+        );
+        framebuffer.draw_number(x + hits_width + DIGIT_WIDTH + 1, y, out_of as u32, WHITE);
+    }
+
+    if let Some(seed) = state.seed {
+        framebuffer.draw_number(
+            hud_left_edge + 2,
+            SCREEN_HEIGHT - DIGIT_HEIGHT - 4,
+            seed,
+            WHITE,
+        );
+    }
+
+    match state.scene {
+        Scene::Paused => screens::render_paused(framebuffer),
+        Scene::LevelComplete => screens::render_level_complete(framebuffer, state.board),
+        Scene::GameOver => {
+            screens::render_game_over(framebuffer, state, remaining_aspects(state));
+        }
+        Scene::Title | Scene::Playing => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constituent_count(state: &GameState) -> usize {
+        (0..GameState::ENTITY_COUNT)
+            .filter(|&id| state.is_orb(id))
+            .map(|id| state.constituents_of(id).as_slice().len())
+            .sum()
+    }
+
+    #[test]
+    fn splitting_a_blob_with_no_room_keeps_what_does_not_fit() {
+        let mut state = level::parse(
+            "aspects 3\nboard 2 1\navatar 0 0\norb blob red 1 0 four 0 0 of \
+             live-red live-white live-black live-red live-white live-black \
+             live-red live-white live-black\n",
+        ).unwrap_or_else(|e| panic!("{}", e));
+
+        state.split_blobs_at((1, 0));
+
+        assert_eq!(constituent_count(&state), 9);
+    }
+
+    #[test]
+    fn live_orbs_can_move_into_each_other_and_fuse() {
+        let mut state = level::parse(
+            "aspects 3\nboard 3 1\navatar 0 0\n\
+             orb live red 2 0 nine 0 1\norb live white 2 0 nine 1 1\n",
+        ).unwrap_or_else(|e| panic!("{}", e));
+        let red = (0..GameState::ENTITY_COUNT)
+            .find(|&id| state.is_orb(id) && state.aspects[id] == Aspect::Red)
+            .unwrap();
+
+        assert!(state.move_within_cell(red, Nine(_3by3::_1_1)));
+        fuse_orbs(&mut state);
+
+        let orbs: Vec<usize> = (0..GameState::ENTITY_COUNT)
+            .filter(|&id| state.is_orb(id))
+            .collect();
+        assert_eq!(orbs.len(), 1);
+        assert!(state.orb_types[orbs[0]] == OrbType::Blob);
+    }
+
+    fn ray_from_red_to_black(terrain: &str) -> (Position, Option<Aspect>) {
+        let state = level::parse(&format!(
+            "aspects 3\nboard 3 1\nterrain\n{}\navatar 0 0\n\
+             orb live red 0 0 nine 0 0\norb dead white 0 0 nine 1 1\n\
+             orb dead black 2 0 nine 1 1\n",
+            terrain
+        )).unwrap_or_else(|e| panic!("{}", e));
+        let orb = |aspect| {
+            (0..GameState::ENTITY_COUNT)
+                .find(|&id| state.is_orb(id) && state.aspects[id] == aspect)
+                .unwrap()
+        };
+
+        let ray = state.cast_ray(orb(Aspect::Red), orb(Aspect::Black));
+
+        (ray.end, ray.hit.map(|id| state.aspects[id]))
+    }
+
+    #[test]
+    fn rays_pass_what_shares_the_casters_cell() {
+        assert_eq!(ray_from_red_to_black("..."), ((2, 0), Some(Aspect::Black)));
+    }
+
+    #[test]
+    fn rays_stop_at_walls() {
+        assert_eq!(ray_from_red_to_black(".#."), ((0, 0), None));
+    }
+
+    #[test]
+    fn activating_a_created_orb_with_no_room_keeps_what_does_not_fit() {
+        let mut state = level::parse(
+            "aspects 3\nboard 2 1\navatar 0 0\norb created red 1 0 four 0 0 of \
+             live-red live-white live-black live-red live-white live-black \
+             live-red live-white live-black\n",
+        ).unwrap_or_else(|e| panic!("{}", e));
+
+        state.activate_created_orbs_at((1, 0));
+
+        assert_eq!(constituent_count(&state), 9);
+    }
+    #[test]
+    fn throwing_the_last_aspect_at_a_blob_wins() {
+        let mut state = level::parse(
+            "aspects 3\nboard 3 1\navatar 0 0\n\
+             orb blob red 2 0 four 0 0 of live-red live-white\ninventory live black\n",
+        ).unwrap_or_else(|e| panic!("{}", e));
+        state.positions[state.selectrixId] = (2, 0);
+        state.intra_cell_positions[state.selectrixId] = Four(_2by2::_0_0);
+
+        assert!(state.throw_selected_orb());
+        advance_world_turn(&mut state);
+
+        assert_eq!(state.scene, Scene::LevelComplete);
+    }
+    #[test]
+    fn throws_stop_short_of_walls() {
+        let mut state = level::parse(
+            "aspects 2\nboard 4 1\nterrain\n..#.\navatar 0 0\ninventory live red\n",
+        ).unwrap_or_else(|e| panic!("{}", e));
+        state.positions[state.selectrixId] = (3, 0);
+
+        assert_eq!(state.throw_landing().0, (1, 0));
+    }
+    #[test]
+    fn start_on_game_over_restarts_the_same_generated_level() {
+        let mut state = generator::generate(42);
+        let exported = level::export(&state);
+        state.scene = Scene::GameOver;
+
+        let mut input = Input::new();
+        input.gamepad = Button::Start;
+        update(&mut state, input);
+
+        assert_eq!(state.scene, Scene::Playing);
+        assert_eq!(level::export(&state), exported);
+    }
+    #[test]
+    fn throws_come_down_on_the_first_thing_in_the_way() {
+        let mut state = level::parse(
+            "aspects 2\nboard 4 1\navatar 0 0\norb dead white 1 0 nine 1 1\n\
+             inventory live red\n",
+        ).unwrap_or_else(|e| panic!("{}", e));
+        state.positions[state.selectrixId] = (3, 0);
+
+        assert_eq!(state.throw_landing().0, (1, 0));
+    }
+
+    //The preview for a live red orb thrown at the top left of a cell with a dead white orb in
+    //`slot`, and whether the throw then hits it.
+    fn preview_then_throw(slot: &str) -> (ThrowPreview, bool) {
+        let mut state = level::parse(&format!(
+            "aspects 2\nboard 3 1\navatar 0 0\norb dead white 2 0 {}\ninventory live red\n",
+            slot
+        )).unwrap_or_else(|e| panic!("{}", e));
+        state.positions[state.selectrixId] = (2, 0);
+        state.intra_cell_positions[state.selectrixId] = Four(_2by2::_0_0);
+
+        let preview = state.throw_preview().unwrap();
+
+        assert!(state.throw_selected_orb());
+        advance_world_turn(&mut state);
+        let white_is_untouched = (0..GameState::ENTITY_COUNT).any(|id| {
+            state.is_orb(id) && state.orb_types[id] == OrbType::DeadOrb
+                && state.healths[id] == MAX_HEALTH
+        });
+
+        (preview, !white_is_untouched)
+    }
+
+    #[test]
+    fn the_throw_preview_shows_a_miss_as_a_miss() {
+        let (preview, was_hit) = preview_then_throw("nine 1 1");
+
+        assert!(!was_hit);
+        assert!(preview.targets.is_empty());
+        assert_eq!(preview.chance, Some((0, 1)));
+    }
+
+    #[test]
+    fn the_throw_preview_shows_a_hit_as_a_hit() {
+        let (preview, was_hit) = preview_then_throw("four 0 0");
+
+        assert!(was_hit);
+        assert_eq!(preview.targets.len(), 1);
+        assert_eq!(preview.chance, Some((1, 1)));
+    }
+}
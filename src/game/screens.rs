@@ -0,0 +1,137 @@
+use common::*;
+
+//There's no font apart from digits, so the screens get by on shapes.
+
+pub fn render_title(framebuffer: &mut Framebuffer) {
+    framebuffer.draw_filled_rect(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, PURPLE);
+
+    //the stages an orb goes through, in each of the first three aspects
+    let stages = [
+        OrbType::DeadOrb,
+        OrbType::LiveOrb,
+        OrbType::Blob,
+        OrbType::CreatedOrb,
+    ];
+    let left = SCREEN_WIDTH / 2 - CELL_WIDTH * 3 / 2;
+    let top = SCREEN_HEIGHT / 2 - CELL_HEIGHT * 2;
+
+    for (row, &aspect) in [Aspect::Red, Aspect::White, Aspect::Black].iter().enumerate() {
+        for (column, orb_type) in stages.iter().enumerate() {
+            if let Some(shape) = orb_type.shape() {
+                let appearance = Appearance {
+                    colour: aspect.colour(),
+                    shape,
+                    offset: (0, 0),
+                    charge: 0,
+                };
+
+                appearance.render_at_px(
+                    framebuffer,
+                    left + column * CELL_WIDTH,
+                    top + row * CELL_HEIGHT,
+                );
+            }
+        }
+    }
+
+    let avatar = Appearance {
+        colour: BLUE,
+        shape: Shape::Player,
+        offset: (0, 0),
+        charge: 0,
+    };
+    avatar.render_at_px(
+        framebuffer,
+        SCREEN_WIDTH / 2 - CELL_WIDTH / 2,
+        top + CELL_HEIGHT * 3,
+    );
+
+    framebuffer.draw_rect(
+        left - CELL_WIDTH,
+        top - CELL_HEIGHT,
+        CELL_WIDTH * 5,
+        CELL_HEIGHT * 5 + CELL_HEIGHT / 2,
+        YELLOW,
+    );
+}
+
+pub fn render_paused(framebuffer: &mut Framebuffer) {
+    framebuffer.blend_filled_rect(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, 0xC0_00_00_00);
+
+    let bar_width = CELL_WIDTH / 3;
+    let top = SCREEN_HEIGHT / 2 - CELL_HEIGHT / 2;
+
+    for &x in [SCREEN_WIDTH / 2 - bar_width * 3 / 2, SCREEN_WIDTH / 2 + bar_width / 2].iter() {
+        framebuffer.draw_filled_rect(x, top, bar_width, CELL_HEIGHT, WHITE);
+    }
+}
+
+pub fn render_level_complete(framebuffer: &mut Framebuffer, board: Board) {
+    let board_width = board.hud_left_edge() - 1;
+    let y = SCREEN_HEIGHT / 2 - CELL_HEIGHT;
+
+    framebuffer.draw_filled_rect(0, y, board_width, CELL_HEIGHT * 2, PURPLE);
+    framebuffer.draw_rect(0, y, board_width, CELL_HEIGHT * 2, YELLOW);
+
+    //you, and the ally you made
+    for &x in [(board_width / 2).saturating_sub(CELL_WIDTH), board_width / 2].iter() {
+        framebuffer.draw_filled_rect(
+            x + 4,
+            y + CELL_HEIGHT / 2 + 4,
+            CELL_WIDTH - 8,
+            CELL_HEIGHT - 6,
+            BLUE,
+        );
+    }
+}
+
+//Shows which aspects are still around, as filled orbs, and which were lost, as outlines,
+//along with how many turns it took.
+pub fn render_game_over(framebuffer: &mut Framebuffer, state: &GameState, remaining_aspects: u32) {
+    let board_width = state.board.hud_left_edge() - 1;
+    let y = SCREEN_HEIGHT / 2 - CELL_HEIGHT;
+
+    framebuffer.blend_filled_rect(0, 0, board_width, SCREEN_HEIGHT, 0x80_00_00_00);
+    framebuffer.draw_filled_rect(0, y, board_width, CELL_HEIGHT * 2, BLACK);
+    framebuffer.draw_rect(0, y, board_width, CELL_HEIGHT * 2, RED);
+
+    let count = state.aspect_count as usize;
+    let spacing = ORB_RADIUS * 4;
+    let left = (board_width / 2).saturating_sub(spacing * count.saturating_sub(1) / 2);
+
+    for i in 0..count {
+        let aspect = Aspect(i as u8);
+        let shape = if remaining_aspects & 1 << i != 0 {
+            Shape::LiveOrb0
+        } else {
+            Shape::DeadOrb0
+        };
+
+        let appearance = Appearance {
+            colour: aspect.colour(),
+            shape,
+            offset: (0, 0),
+            charge: 0,
+        };
+        appearance.render_at_px(framebuffer, left + i * spacing, y + CELL_HEIGHT / 2);
+    }
+
+    framebuffer.draw_number(
+        (board_width / 2).saturating_sub(DIGIT_WIDTH * 2),
+        y + CELL_HEIGHT + CELL_HEIGHT / 4,
+        state.turn,
+        WHITE,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_level_complete_screen_fits_the_narrowest_board() {
+        let board = Board::new(1, 1).unwrap();
+
+        render_level_complete(&mut Framebuffer::new(), board);
+    }
+}
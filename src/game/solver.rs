@@ -0,0 +1,342 @@
+use common::*;
+
+use super::{advance_world_turn, interactions};
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+//Enough to get through a small level, without making the tests take too long.
+pub const DEFAULT_MAX_STATES: usize = 20_000;
+
+//Something the player can do that takes a world turn.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    Move(Direction),
+    //the inventory slot, and where the selectrix was when it was thrown
+    Throw(usize, Position, _2by2),
+}
+
+pub enum Verdict {
+    //the shortest way there is to win
+    Solved(Vec<Action>),
+    //every state that can be reached was checked, and none of them are won
+    Unsolvable,
+    //ran out of states to explore before finding out either way
+    GaveUp,
+}
+
+pub struct Report {
+    pub verdict: Verdict,
+    pub states_explored: usize,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.verdict {
+            Verdict::Solved(ref actions) => {
+                write!(f, "solved in {} turns: {:?}", actions.len(), actions)?
+            }
+            Verdict::Unsolvable => write!(f, "unsolvable")?,
+            Verdict::GaveUp => write!(f, "gave up")?,
+        }
+
+        write!(f, ", after exploring {} states", self.states_explored)
+    }
+}
+
+//Searches breadth first from `state`, so the first solution found is one of the shortest.
+//Only the action that led to each state is kept, not the state itself, so a state gets
+//rebuilt by replaying the actions from the start when it's time to explore it.
+pub fn solve(state: &GameState, max_states: usize) -> Report {
+    let start = state.without_history();
+
+    if start.scene == Scene::LevelComplete {
+        return Report {
+            verdict: Verdict::Solved(Vec::new()),
+            states_explored: 0,
+        };
+    }
+
+    if start.scene != Scene::Playing || !could_ever_be_won(&start) {
+        return Report {
+            verdict: Verdict::Unsolvable,
+            states_explored: 0,
+        };
+    }
+
+    //what led to each state found, as (the index of the one before it, the action taken)
+    let mut steps: Vec<(Option<usize>, Action)> = Vec::new();
+    let mut seen = HashSet::new();
+    seen.insert(key(&start));
+
+    let mut queue = VecDeque::new();
+    queue.push_back(None);
+
+    let mut states_explored = 0;
+    while let Some(index) = queue.pop_front() {
+        if states_explored >= max_states {
+            return Report {
+                verdict: Verdict::GaveUp,
+                states_explored,
+            };
+        }
+        states_explored += 1;
+
+        let mut current = start.clone();
+        for action in solution(&steps, index) {
+            take_turn(&mut current, action);
+        }
+
+        for action in actions(&current) {
+            let mut next = current.clone();
+            if !take_turn(&mut next, action) || !seen.insert(key(&next)) {
+                continue;
+            }
+
+            steps.push((index, action));
+            let next_index = Some(steps.len() - 1);
+
+            match next.scene {
+                Scene::LevelComplete => {
+                    return Report {
+                        verdict: Verdict::Solved(solution(&steps, next_index)),
+                        states_explored,
+                    };
+                }
+                Scene::Playing => queue.push_back(next_index),
+                _ => {}
+            }
+        }
+    }
+
+    Report {
+        verdict: Verdict::Unsolvable,
+        states_explored,
+    }
+}
+
+//Everything about a state that can change what happens next, packed tightly, since one of
+//these is kept for every state found. The terrain never changes, and the selectrix and
+//selected slot are set by each throw, so they're left out.
+fn key(state: &GameState) -> Vec<u8> {
+    let mut key = vec![state.scene as u8];
+
+    let ids = (0..GameState::ENTITY_COUNT)
+        .filter(|&id| id != state.selectrixId && !state.entities[id].is_empty());
+    for id in ids {
+        let bits = state.entities[id].bits();
+        let (left, top, right, bottom) = state.intra_cell_positions[id].sixths_rect();
+
+        key.extend_from_slice(&[
+            id as u8,
+            bits as u8,
+            (bits >> 8) as u8,
+            state.positions[id].0,
+            state.positions[id].1,
+            left,
+            top,
+            right,
+            bottom,
+            state.player_types[id] as u8,
+            state.orb_types[id] as u8,
+            state.aspects[id].0,
+            state.charges[id],
+            state.healths[id],
+        ]);
+        push_constituents(&mut key, &state.constituents[id]);
+    }
+
+    for item_index in 0..INVENTORY_SIZE {
+        push_item(&mut key, state.inventory.get(item_index));
+    }
+
+    key
+}
+
+fn push_item(key: &mut Vec<u8>, item: &InventoryItem) {
+    key.extend_from_slice(&[item.orb_type as u8, item.aspect.0, item.charge, item.health]);
+    push_constituents(key, &item.constituents);
+}
+
+fn push_constituents(key: &mut Vec<u8>, constituents: &Constituents) {
+    key.push(constituents.as_slice().len() as u8);
+    for constituent in constituents.as_slice() {
+        key.extend_from_slice(&[constituent.orb_type as u8, constituent.aspect.0]);
+    }
+}
+
+//Whether `interactions::INTERACTIONS` could ever gather every aspect into one orb, given the
+//orbs there are now. It only goes by how many aspects each stage could hold, so this can say
+//yes when there's no way to do it, but never no when there is.
+fn could_ever_be_won(state: &GameState) -> bool {
+    let aspect_count = state.aspect_count as usize;
+    let mut most_aspects = [0; OrbType::STAGE_COUNT];
+
+    let mut note = |orb_type: OrbType, aspects: u32| {
+        if let Some(stage) = orb_type.stage_index() {
+            most_aspects[stage] = most_aspects[stage].max(aspects.count_ones() as usize);
+        }
+    };
+
+    for id in (0..GameState::ENTITY_COUNT).filter(|&id| state.is_orb(id)) {
+        note(state.orb_types[id], state.constituents_of(id).aspect_mask());
+    }
+    for item_index in 0..INVENTORY_SIZE {
+        let item = state.inventory.get(item_index);
+        note(item.orb_type, 1 << item.aspect.0 | item.constituents.aspect_mask());
+    }
+
+    let stage = |orb_type: OrbType| orb_type.stage_index().unwrap_or(0);
+
+    //dead orbs recharge into live ones, which is the only way to change stage without meeting
+    //another orb
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        let recharged = most_aspects[stage(OrbType::DeadOrb)];
+        let mut growths = vec![(OrbType::LiveOrb, recharged)];
+
+        for rule in interactions::INTERACTIONS.iter() {
+            let first = most_aspects[stage(rule.first.0)];
+            let second = most_aspects[stage(rule.second.0)];
+            if first > 0 && second > 0 {
+                growths.push((rule.produces, (first + second).min(aspect_count)));
+            }
+        }
+
+        for (orb_type, aspects) in growths {
+            let most = &mut most_aspects[stage(orb_type)];
+            if aspects > *most {
+                *most = aspects;
+                changed = true;
+            }
+        }
+    }
+
+    most_aspects.iter().any(|&aspects| aspects >= aspect_count)
+}
+
+fn solution(steps: &[(Option<usize>, Action)], mut index: Option<usize>) -> Vec<Action> {
+    let mut actions = Vec::new();
+
+    while let Some(i) = index {
+        let (previous, action) = steps[i];
+        actions.push(action);
+        index = previous;
+    }
+
+    actions.reverse();
+    actions
+}
+
+//Every item can be thrown at every slot of every cell, even ones where there's nothing to hit,
+//since where it lands can still matter later on. Throws that would leave the same orb in the
+//same place only need trying once, though.
+fn actions(state: &GameState) -> Vec<Action> {
+    let mut actions: Vec<Action> = Direction::ALL.iter().map(|&d| Action::Move(d)).collect();
+
+    let mut throws = Vec::new();
+    for item_index in 0..INVENTORY_SIZE {
+        let item = state.inventory.get(item_index);
+        if item.is_empty() {
+            continue;
+        }
+
+        let mut item_key = Vec::new();
+        push_item(&mut item_key, item);
+
+        for y in 0..state.board.height {
+            for x in 0..state.board.width {
+                for &slot in _2by2::ALL.iter() {
+                    let (landing, _) = state.throw_landing_at((x, y), Four(slot));
+
+                    let throw = (item_key.clone(), landing, slot);
+                    if !throws.contains(&throw) {
+                        throws.push(throw);
+                        actions.push(Action::Throw(item_index, (x, y), slot));
+                    }
+                }
+            }
+        }
+    }
+
+    actions
+}
+
+//`false` if the action wouldn't take a turn.
+fn take_turn(state: &mut GameState, action: Action) -> bool {
+    let took_turn = match action {
+        Action::Move(direction) => state.move_avatar(direction),
+        Action::Throw(item_index, pos, slot) => {
+            state.inventory.index = item_index as u8;
+            state.positions[state.selectrixId] = pos;
+            state.intra_cell_positions[state.selectrixId] = Four(slot);
+
+            state.throw_selected_orb()
+        }
+    };
+
+    if !took_turn {
+        return false;
+    }
+
+    state.turn = state.turn.wrapping_add(1);
+    advance_world_turn(state);
+    state.history = History::default();
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Bundled levels, by index, with more going on than the search can get through. All that can
+    //be checked for these is that nothing rules them out.
+    const TOO_BIG_TO_SEARCH: [usize; 1] = [0];
+
+    fn verdict_for(text: &str) -> Verdict {
+        let state = level::parse(text).unwrap_or_else(|e| panic!("{}", e));
+
+        solve(&state, DEFAULT_MAX_STATES).verdict
+    }
+
+    #[test]
+    fn every_bundled_level_can_be_won() {
+        for (i, text) in level::BUNDLED_LEVELS.iter().enumerate() {
+            let state = level::parse(text).unwrap_or_else(|e| panic!("{}", e));
+            let report = solve(&state, DEFAULT_MAX_STATES);
+
+            match report.verdict {
+                Verdict::Solved(_) => {}
+                Verdict::GaveUp if TOO_BIG_TO_SEARCH.contains(&i) => {}
+                _ => panic!("bundled level {}: {}", i, report),
+            }
+        }
+    }
+
+    #[test]
+    fn a_live_orb_in_hand_wins_in_one_throw() {
+        let level = "aspects 2\nboard 3 1\navatar 0 0\norb dead white 2 0 four 0 0\n\
+                     inventory live red\n";
+
+        match verdict_for(level) {
+            Verdict::Solved(actions) => {
+                assert_eq!(actions, vec![Action::Throw(0, (2, 0), _2by2::_0_0)]);
+            }
+            _ => panic!("expected a solution"),
+        }
+    }
+
+    #[test]
+    fn a_missing_aspect_makes_a_level_unsolvable() {
+        let level = "aspects 3\nboard 3 1\navatar 0 0\norb dead white 2 0 four 0 0\n\
+                     inventory live red\n";
+
+        match verdict_for(level) {
+            Verdict::Unsolvable => {}
+            _ => panic!("expected no solution"),
+        }
+    }
+}
@@ -8,6 +8,10 @@ extern crate bitflags;
 #[macro_use]
 extern crate stdweb;
 
+#[cfg(test)]
+#[macro_use]
+extern crate quickcheck;
+
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::error::Error;
@@ -223,6 +227,10 @@ impl PinkyWeb {
             return h;
         );
 
+        for problem in game::interaction_problems() {
+            js!( console.warn( @{problem} ); );
+        }
+
         PinkyWeb {
             state: State::new(),
             paused: true,
@@ -294,6 +302,13 @@ impl PinkyWeb {
     }
 
     fn on_key(&mut self, key: &str, location: KeyboardLocation, is_pressed: bool) -> bool {
+        // Not one of the game's buttons, just a way for debug builds to save the current board
+        // as a level.
+        if cfg!(debug_assertions) && key == "p" && is_pressed {
+            js!( console.log( @{level::export( &self.state.game_state )} ); );
+            return true;
+        }
+
         let button = match (key, location) {
             ("Enter", _) => Button::Start,
             ("Shift", KeyboardLocation::Right) => Button::Select,